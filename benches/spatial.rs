@@ -0,0 +1,55 @@
+//! compares the old linear scan over every item with `SpatialIndex`
+//! run with `cargo bench --bench spatial`
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use rand::Rng;
+use void_a_nomicon::item::{physics::box_point_hit, spatial::SpatialIndex};
+
+const QUERIES: usize = 10_000;
+
+fn main() {
+    for count in [100, 1_000, 5_000, 20_000] {
+        let mut rng = rand::thread_rng();
+        let extent = (count as f32).sqrt() * 60.;
+        let size = Vec2::splat(100.);
+        let items: Vec<(Entity, Vec3)> = (0..count).map(|i| {
+            (Entity::from_raw(i as u32), Vec3::new(rng.gen_range(-extent..extent), rng.gen_range(-extent..extent), i as f32 * 0.001))
+        }).collect();
+        let points: Vec<Vec2> = (0..QUERIES).map(|_| Vec2::new(rng.gen_range(-extent..extent), rng.gen_range(-extent..extent))).collect();
+
+        let mut index = SpatialIndex::default();
+        for (e, at) in items.iter() {
+            index.insert(*e, *at, size);
+        }
+
+        let linear = time(|| {
+            let mut found = 0;
+            for point in points.iter() {
+                let mut best: Option<(Entity, f32)> = None;
+                for (e, at) in items.iter() {
+                    if box_point_hit(size, at.truncate(), *point) && best.map_or(true, |(_, z)| at.z > z) {
+                        best = Some((*e, at.z));
+                    }
+                }
+                if best.is_some() {found += 1;}
+            }
+            found
+        });
+        let grid = time(|| {
+            points.iter().filter(|p| index.top_at_point(**p).is_some()).count()
+        });
+        assert_eq!(linear.1, grid.1, "index and linear scan disagree");
+        println!(
+            "{:>6} items: linear {:>10.3?}  index {:>10.3?}  speedup {:>7.1}x",
+            count, linear.0 / QUERIES as u32, grid.0 / QUERIES as u32,
+            linear.0.as_secs_f64() / grid.0.as_secs_f64().max(f64::EPSILON),
+        );
+    }
+}
+
+fn time(f: impl FnOnce() -> usize) -> (Duration, usize) {
+    let start = Instant::now();
+    let res = f();
+    (start.elapsed(), res)
+}
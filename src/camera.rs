@@ -0,0 +1,264 @@
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    input::mouse::MouseWheel,
+    render::{camera::Viewport, view::RenderLayers},
+    window::{WindowResized, WindowScaleFactorChanged},
+};
+
+use crate::{prelude::*, view::BoardView};
+
+#[cfg(test)]
+mod test {
+    use bevy::{prelude::*, input::{InputPlugin, touch::{TouchInput, TouchPhase}}};
+    use crate::{MainCam, view::BoardView};
+    use super::{pinch_zoom, minimap_viewport, MINIMAP_SIZE};
+
+    fn touch(app: &mut App, id: u64, phase: TouchPhase, position: Vec2) {
+        app.world.resource_mut::<Events<TouchInput>>().send(TouchInput { phase, position, force: None, id });
+    }
+
+    #[test]
+    fn pinch() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(InputPlugin)
+            .init_resource::<BoardView>()
+            .add_system_to_stage(CoreStage::PreUpdate, pinch_zoom.after(bevy::input::InputSystem));
+        let camera = app.world.spawn()
+            .insert(Transform::default())
+            .insert(OrthographicProjection::default())
+            .insert(MainCam)
+            .id();
+        touch(&mut app, 1, TouchPhase::Started, Vec2::new(590., 360.));
+        touch(&mut app, 2, TouchPhase::Started, Vec2::new(690., 360.));
+        app.update();
+        // fingers twice as far apart is twice as close
+        touch(&mut app, 1, TouchPhase::Moved, Vec2::new(540., 360.));
+        touch(&mut app, 2, TouchPhase::Moved, Vec2::new(740., 360.));
+        app.update();
+        let scale = app.world.get::<OrthographicProjection>(camera).unwrap().scale;
+        assert!((scale - 0.5).abs() < 0.001, "scale was {}", scale);
+        touch(&mut app, 2, TouchPhase::Ended, Vec2::new(740., 360.));
+        app.update();
+        touch(&mut app, 1, TouchPhase::Moved, Vec2::new(100., 360.));
+        app.update();
+        let scale = app.world.get::<OrthographicProjection>(camera).unwrap().scale;
+        assert!((scale - 0.5).abs() < 0.001, "one finger does not zoom");
+    }
+
+    #[test]
+    fn minimap_above_tooltip() {
+        for (size, scale) in [(Vec2::new(1280., 720.), 1.), (Vec2::new(800., 600.), 2.)] {
+            let viewport = minimap_viewport(size, scale);
+            // viewports count down from the top of the window
+            let bottom = (viewport.physical_position.y + viewport.physical_size.y) as f32 / scale;
+            assert!(bottom <= size.y - crate::ui::TOOLTIP_HEIGHT);
+            assert_eq!(viewport.physical_size.as_vec2() / scale, MINIMAP_SIZE);
+        }
+    }
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_minimap);
+        app.add_system(pan_camera);
+        app.add_system(zoom_camera);
+        app.add_system(pinch_zoom);
+        app.add_system(update_minimap);
+        app.add_system(resize_minimap);
+    }
+}
+
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 4.0;
+const PAN_SPEED: f32 = 600.;
+/// logical size of the minimap in the bottom left corner
+const MINIMAP_SIZE: Vec2 = Vec2::new(200., 120.);
+/// only the minimap camera can see this layer
+const MINIMAP_LAYER: u8 = 1;
+
+#[derive(Component)]
+pub struct MiniMapCam;
+
+/// outline of what the main camera can see, drawn on the minimap
+#[derive(Component)]
+struct ViewFrame;
+
+fn pan_camera(
+    mut camera: Query<(&mut Transform, &OrthographicProjection), With<MainCam>>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    cursor: Res<WorldCursor>,
+    time: Res<Time>,
+    mut last: Local<Option<Vec2>>,
+) {
+    let (mut transform, projection) = if let Ok(c) = camera.get_single_mut() {c} else {return;};
+    let mut delta = Vec2::ZERO;
+    // ctrl + key is a shortcut not a pan
+    if !crate::item::select::ctrl_held(&keys) {
+        if keys.pressed(KeyCode::W) {delta.y += 1.;}
+        if keys.pressed(KeyCode::S) {delta.y -= 1.;}
+        if keys.pressed(KeyCode::A) {delta.x -= 1.;}
+        if keys.pressed(KeyCode::D) {delta.x += 1.;}
+        delta *= PAN_SPEED * time.delta_seconds();
+    }
+    if buttons.pressed(MouseButton::Middle) {
+        if let (Some(last), Some(now)) = (*last, cursor.screen) {
+            delta -= now - last;
+        }
+        *last = cursor.screen;
+    } else {
+        *last = None;
+    }
+    if delta == Vec2::ZERO || !delta.is_finite() {return;}
+    transform.translation += (delta * projection.scale).extend(0.);
+}
+
+fn zoom_camera(
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCam>>,
+    mut events: EventReader<MouseWheel>,
+    cursor: Res<WorldCursor>,
+    view: Res<BoardView>,
+    item_space: Query<(), With<crate::ui::ItemSpace>>,
+) {
+    let mut delta = 0.0;
+    for e in events.iter() {
+        delta += e.y;
+    }
+    if delta == 0. {return;}
+    // the item space panel scrolls instead
+    if let Some(screen) = cursor.screen {
+        if !item_space.is_empty() && crate::ui::over_item_space(screen, view.size) {return;}
+    }
+    let (mut transform, mut projection) = if let Ok(c) = camera.get_single_mut() {c} else {return;};
+    let old = projection.scale;
+    let new = (old * (1.0 - delta.signum() * 0.1)).clamp(MIN_ZOOM, MAX_ZOOM);
+    if new == old {return;}
+    projection.scale = new;
+    // keep whatever is under the cursor under the cursor
+    if let Some(world) = cursor.world {
+        let center = transform.translation.truncate();
+        let center = world - (world - center) * (new / old);
+        transform.translation.x = center.x;
+        transform.translation.y = center.y;
+    }
+}
+
+/// two fingers spreading apart zoom in around the point between them
+fn pinch_zoom(
+    touches: Res<Touches>,
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCam>>,
+    view: Res<BoardView>,
+    mut last: Local<Option<f32>>,
+) {
+    let mut fingers: Vec<_> = touches.iter().collect();
+    if fingers.len() < 2 {
+        *last = None;
+        return;
+    }
+    fingers.sort_by_key(|t| t.id());
+    let (a, b) = (fingers[0].position(), fingers[1].position());
+    let distance = a.distance(b);
+    let before = last.replace(distance);
+    let before = if let Some(d) = before {d} else {return;};
+    if before <= 0. || distance <= 0. {return;}
+    let (mut transform, mut projection) = if let Ok(c) = camera.get_single_mut() {c} else {return;};
+    let old = projection.scale;
+    let new = (old * before / distance).clamp(MIN_ZOOM, MAX_ZOOM);
+    if new == old {return;}
+    projection.scale = new;
+    let world = view.to_world((a + b) / 2.);
+    let center = transform.translation.truncate();
+    let center = world - (world - center) * (new / old);
+    transform.translation.x = center.x;
+    transform.translation.y = center.y;
+}
+
+/// gap between the minimap and the edges around it
+const MINIMAP_MARGIN: f32 = 10.;
+
+/// just above the tooltip bar on the left, the item space takes the right side
+fn minimap_viewport(window_size: Vec2, scale_factor: f32) -> Viewport {
+    // viewports count down from the top of the window
+    let top_left = Vec2::new(MINIMAP_MARGIN, window_size.y - crate::ui::TOOLTIP_HEIGHT - MINIMAP_SIZE.y - MINIMAP_MARGIN).max(Vec2::ZERO);
+    Viewport {
+        physical_position: (top_left * scale_factor).as_uvec2(),
+        physical_size: (MINIMAP_SIZE * scale_factor).as_uvec2(),
+        ..Default::default()
+    }
+}
+
+fn window_viewport(windows: &Windows) -> Viewport {
+    let scale_factor = windows.get_primary().map(|w| w.scale_factor() as f32).unwrap_or(1.);
+    let window_size = windows.get_primary().map(|w| Vec2::new(w.width(), w.height())).unwrap_or(Vec2::new(1280., 720.));
+    minimap_viewport(window_size, scale_factor)
+}
+
+fn spawn_minimap(
+    mut commands: Commands,
+    windows: Res<Windows>,
+) {
+    commands.spawn_bundle(Camera2dBundle {
+        camera: Camera {
+            priority: 1,
+            viewport: Some(window_viewport(&windows)),
+            ..Default::default()
+        },
+        camera_2d: Camera2d { clear_color: ClearColorConfig::Custom(Color::rgba(0.1, 0.1, 0.1, 1.0)) },
+        ..Default::default()
+    })
+    .insert(UiCameraConfig { show_ui: false })
+    .insert(RenderLayers::from_layers(&[0, MINIMAP_LAYER]))
+    .insert(MiniMapCam)
+    .insert(Name::new("MiniMap"));
+    commands.spawn_bundle(SpriteBundle {
+        sprite: Sprite { color: Color::rgba(1., 1., 1., 0.2), custom_size: Some(Vec2::ONE), ..Default::default() },
+        ..Default::default()
+    })
+    .insert(RenderLayers::layer(MINIMAP_LAYER))
+    .insert(ViewFrame);
+}
+
+/// keeps the minimap in its corner when the window changes size
+fn resize_minimap(
+    mut resized: EventReader<WindowResized>,
+    mut scaled: EventReader<WindowScaleFactorChanged>,
+    windows: Res<Windows>,
+    mut minimap: Query<&mut Camera, With<MiniMapCam>>,
+) {
+    let resized = resized.iter().filter(|e| e.id.is_primary()).count();
+    let scaled = scaled.iter().filter(|e| e.id.is_primary()).count();
+    if resized + scaled == 0 {return;}
+    for mut camera in minimap.iter_mut() {
+        camera.viewport = Some(window_viewport(&windows));
+    }
+}
+
+/// fits every item and the current view onto the minimap
+fn update_minimap(
+    view: Res<BoardView>,
+    items: Query<&Transform, (With<ItemID>, With<crate::item::physics::Size>, Without<MiniMapCam>, Without<ViewFrame>)>,
+    mut minimap: Query<(&mut Transform, &mut OrthographicProjection), (With<MiniMapCam>, Without<ViewFrame>)>,
+    mut frame: Query<&mut Transform, (With<ViewFrame>, Without<MiniMapCam>)>,
+) {
+    let view_half = view.size * view.scale / 2.;
+    let mut min = view.center - view_half;
+    let mut max = view.center + view_half;
+    for t in items.iter() {
+        min = min.min(t.translation.truncate() - 50.);
+        max = max.max(t.translation.truncate() + 50.);
+    }
+    if let Ok(mut t) = frame.get_single_mut() {
+        t.translation = view.center.extend(900.);
+        t.scale = (view_half * 2.).extend(1.);
+    }
+    if let Ok((mut t, mut projection)) = minimap.get_single_mut() {
+        let center = (min + max) / 2.;
+        t.translation.x = center.x;
+        t.translation.y = center.y;
+        let fit = (max - min) / MINIMAP_SIZE;
+        projection.scale = fit.x.max(fit.y) * 1.1;
+    }
+}
@@ -0,0 +1,370 @@
+use serde::{Serialize, Deserialize};
+
+use crate::{prelude::*, view::BoardView, item::{physics::Seleced, pickup::DragTrail, history::History, config::ItemConfig, layers::part}, ui::{ItemSpace, ItemSpaceItem, ITEM_SPACE_SIZE, take_out}};
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use super::nearest;
+
+    #[test]
+    fn nearest_in_direction() {
+        let items = [
+            (Entity::from_raw(1), Vec2::new(100., 0.)),
+            (Entity::from_raw(2), Vec2::new(300., 10.)),
+            (Entity::from_raw(3), Vec2::new(50., 200.)),
+            (Entity::from_raw(4), Vec2::new(-20., 0.)),
+        ];
+        assert_eq!(nearest(Vec2::ZERO, Vec2::X, items.into_iter()), Some(Entity::from_raw(1)));
+        assert_eq!(nearest(Vec2::ZERO, Vec2::Y, items.into_iter()), Some(Entity::from_raw(3)));
+        assert_eq!(nearest(Vec2::ZERO, -Vec2::X, items.into_iter()), Some(Entity::from_raw(4)));
+        assert_eq!(nearest(Vec2::ZERO, -Vec2::Y, items.into_iter()), None);
+    }
+}
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Controls>();
+        app.init_resource::<Focus>();
+        app.add_system_to_stage(CoreStage::PreUpdate, focus_cursor.after(crate::view::touch_cursor));
+        app.add_system(navigate);
+        app.add_system(mark_focus.after(navigate));
+        app.add_system(scroll_to_focus.after(navigate));
+    }
+}
+
+/// a key or a gamepad button, any connected gamepad works
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Pad(GamepadButtonType),
+}
+
+/// what each action is bound to, loaded from `controls.config`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Controls {
+    pub up: Vec<Binding>,
+    pub down: Vec<Binding>,
+    pub left: Vec<Binding>,
+    pub right: Vec<Binding>,
+    /// pick up, put down or spawn from the item space
+    pub grab: Vec<Binding>,
+    /// move focus between the board and the item space
+    pub switch: Vec<Binding>,
+    /// put down anything held and give control back to the mouse
+    pub cancel: Vec<Binding>,
+    /// how fast a held item moves in pixels a second
+    #[serde(default = "default_carry_speed")]
+    pub carry_speed: f32,
+}
+
+fn default_carry_speed() -> f32 {
+    400.
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        use Binding::*;
+        Controls {
+            up: vec![Key(KeyCode::Up), Pad(GamepadButtonType::DPadUp)],
+            down: vec![Key(KeyCode::Down), Pad(GamepadButtonType::DPadDown)],
+            left: vec![Key(KeyCode::Left), Pad(GamepadButtonType::DPadLeft)],
+            right: vec![Key(KeyCode::Right), Pad(GamepadButtonType::DPadRight)],
+            grab: vec![Key(KeyCode::Space), Key(KeyCode::Return), Pad(GamepadButtonType::South)],
+            switch: vec![Key(KeyCode::Tab), Pad(GamepadButtonType::North)],
+            cancel: vec![Key(KeyCode::Escape), Pad(GamepadButtonType::East)],
+            carry_speed: default_carry_speed(),
+        }
+    }
+}
+
+impl FromWorld for Controls {
+    fn from_world(_: &mut World) -> Self {
+        match load_controls() {
+            Ok(controls) => controls,
+            Err(GameError::IOError(_)) => Controls::default(),
+            Err(e) => {
+                error!("controls.config: {}", e);
+                Controls::default()
+            },
+        }
+    }
+}
+
+fn load_controls() -> Result<Controls, GameError> {
+    let data = std::fs::read_to_string("./assets/controls.config")?;
+    Ok(ron::from_str(&data)?)
+}
+
+/// the buttons the player has, read from the keyboard and every gamepad
+struct Pressed<'a> {
+    keys: &'a Input<KeyCode>,
+    buttons: &'a Input<GamepadButton>,
+    gamepads: &'a Gamepads,
+}
+
+impl Pressed<'_> {
+    fn just_pressed(&self, bindings: &[Binding]) -> bool {
+        bindings.iter().any(|b| match b {
+            Binding::Key(key) => self.keys.just_pressed(*key),
+            Binding::Pad(button) => self.gamepads.iter().any(|pad| self.buttons.just_pressed(GamepadButton(*pad, *button))),
+        })
+    }
+
+    fn pressed(&self, bindings: &[Binding]) -> bool {
+        bindings.iter().any(|b| match b {
+            Binding::Key(key) => self.keys.pressed(*key),
+            Binding::Pad(button) => self.gamepads.iter().any(|pad| self.buttons.pressed(GamepadButton(*pad, *button))),
+        })
+    }
+
+    fn direction(&self, controls: &Controls, held: bool) -> Vec2 {
+        let check = |b: &[Binding]| if held {self.pressed(b)} else {self.just_pressed(b)};
+        let mut dir = Vec2::ZERO;
+        if check(&controls.up) {dir.y += 1.;}
+        if check(&controls.down) {dir.y -= 1.;}
+        if check(&controls.left) {dir.x -= 1.;}
+        if check(&controls.right) {dir.x += 1.;}
+        dir
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// an item on the board
+    Board(Entity),
+    /// a button in the item space
+    Slot(Entity),
+}
+
+/// what the keyboard or gamepad is pointing at, while `active` it stands in for the mouse
+#[derive(Debug, Default)]
+pub struct Focus {
+    pub target: Option<Target>,
+    pub active: bool,
+    /// world position the cursor is held at
+    point: Vec2,
+    /// where the real mouse was when the keyboard took over
+    mouse: Option<Vec2>,
+    /// marker showing the focus and what it is on
+    marker: Option<(Entity, Target)>,
+}
+
+/// while the keyboard has control the cursor sits on the focus, moving the mouse takes it back
+pub fn focus_cursor(
+    mut focus: ResMut<Focus>,
+    mut cursor: ResMut<WorldCursor>,
+    view: Res<BoardView>,
+    mouse: Res<Input<MouseButton>>,
+) {
+    if !focus.active {return;}
+    if cursor.screen != focus.mouse || cursor.touch || mouse.get_just_pressed().next().is_some() {
+        focus.active = false;
+        return;
+    }
+    cursor.world = Some(focus.point);
+    cursor.screen = Some(view.to_screen(focus.point));
+}
+
+/// the closest of `options` from `from` that is roughly in direction `dir`
+fn nearest(from: Vec2, dir: Vec2, options: impl Iterator<Item = (Entity, Vec2)>) -> Option<Entity> {
+    options
+        .filter_map(|(e, at)| {
+            let delta = at - from;
+            let along = delta.dot(dir);
+            // more than 45 degrees off is not in that direction
+            if along <= 0. || along * along < delta.length_squared() * 0.5 {return None;}
+            Some((e, delta.length_squared()))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(e, _)| e)
+}
+
+pub fn navigate(
+    mut focus: ResMut<Focus>,
+    controls: Res<Controls>,
+    (keys, buttons, gamepads): (Res<Input<KeyCode>>, Res<Input<GamepadButton>>, Res<Gamepads>),
+    mut cursor: ResMut<WorldCursor>,
+    view: Res<BoardView>,
+    held: Res<Seleced>,
+    board: Query<(Entity, &Transform), (With<ItemID>, With<Size>)>,
+    slots: Query<(Entity, &ItemID, &GlobalTransform), With<ItemSpaceItem>>,
+    mut trail: ResMut<DragTrail>,
+    mut events: EventWriter<ItemEvent>,
+    (mut history, config): (ResMut<History>, Res<ItemConfig>),
+    time: Res<Time>,
+) {
+    let input = Pressed { keys: &keys, buttons: &buttons, gamepads: &gamepads };
+    let holding = held.0.is_some() && focus.active;
+    let dir = input.direction(&controls, holding);
+    let grab = input.just_pressed(&controls.grab);
+    let switch = input.just_pressed(&controls.switch);
+    let cancel = input.just_pressed(&controls.cancel);
+    if dir == Vec2::ZERO && !grab && !switch && !cancel {
+        // keep up with the item moving under the focus
+        if let (Some(Target::Board(e)), false) = (focus.target, holding) {
+            if let Ok((_, t)) = board.get(e) {focus.point = t.translation.truncate();}
+        }
+        return;
+    }
+    if cancel {
+        if holding {
+            trail.clear();
+            events.send(ItemEvent::Drop);
+        }
+        focus.active = false;
+        focus.target = None;
+        return;
+    }
+    if !focus.active {
+        focus.active = true;
+        focus.point = cursor.world.unwrap_or(view.center);
+        // control goes back to the mouse once it moves away from here
+        focus.mouse = cursor.screen;
+    }
+    let board_at = |e: Entity| board.get(e).ok().map(|(_, t)| t.translation.truncate());
+    let slot_at = |e: Entity| slots.get(e).ok().map(|(_, _, t)| t.translation().truncate());
+    // focus on something that is gone starts again from where it was
+    let target = match focus.target {
+        Some(Target::Board(e)) if board_at(e).is_some() => Some(Target::Board(e)),
+        Some(Target::Slot(e)) if slot_at(e).is_some() => Some(Target::Slot(e)),
+        Some(Target::Slot(_)) => slots.iter().next().map(|(e, _, _)| Target::Slot(e)),
+        _ => {
+            let point = focus.point;
+            board.iter()
+                .min_by(|(_, a), (_, b)| a.translation.truncate().distance_squared(point).total_cmp(&b.translation.truncate().distance_squared(point)))
+                .map(|(e, _)| Target::Board(e))
+        },
+    };
+    focus.target = target;
+    if holding {
+        focus.point += dir * controls.carry_speed * view.scale * time.delta_seconds();
+        if grab {
+            // a keyboard release is a put down never a fling
+            trail.clear();
+            events.send(ItemEvent::Drop);
+        }
+        return;
+    }
+    if switch {
+        focus.target = match focus.target {
+            Some(Target::Slot(_)) => board.iter().next().map(|(e, _)| Target::Board(e)),
+            _ => slots.iter().next().map(|(e, _, _)| Target::Slot(e)).or(focus.target),
+        };
+    } else if dir != Vec2::ZERO {
+        focus.target = match focus.target {
+            Some(Target::Board(e)) => {
+                let from = board_at(e).unwrap_or(focus.point);
+                nearest(from, dir, board.iter().filter(|(o, _)| *o != e).map(|(o, t)| (o, t.translation.truncate())))
+                    .map(Target::Board).or(focus.target)
+            },
+            Some(Target::Slot(e)) => {
+                let from = slot_at(e).unwrap_or_default();
+                nearest(from, dir, slots.iter().filter(|(o, _, _)| *o != e).map(|(o, _, t)| (o, t.translation().truncate())))
+                    .map(Target::Slot).or(focus.target)
+            },
+            None => None,
+        };
+    }
+    match focus.target {
+        Some(Target::Board(e)) => {
+            // the tool tip follows the cursor so it already shows this item
+            if let Some(at) = board_at(e) {focus.point = at;}
+            if grab {
+                // `focus_cursor` already ran this frame, so on the first key the cursor is still the mouse
+                // and the item would be held at an offset from it
+                cursor.world = Some(focus.point);
+                cursor.screen = Some(view.to_screen(focus.point));
+                events.send(ItemEvent::Pickup(e));
+            }
+        },
+        Some(Target::Slot(e)) => {
+            if let Ok((_, id, _)) = slots.get(e) {
+                if grab {
+                    events.send(take_out(*id, &mut history, &view, &config));
+                } else {
+                    events.send(ItemEvent::ToolTip(*id));
+                }
+            }
+        },
+        None => {},
+    }
+}
+
+/// on the outline showing what has focus
+#[derive(Component)]
+pub struct FocusMarker;
+
+/// outlines whatever has focus while the keyboard or gamepad is in control
+pub fn mark_focus(
+    mut commands: Commands,
+    mut focus: ResMut<Focus>,
+    markers: Query<(), With<FocusMarker>>,
+    board: Query<&Size, With<ItemID>>,
+    slots: Query<(), With<ItemSpaceItem>>,
+    icons: Res<crate::ui::UiIcons>,
+) {
+    let want = focus.target.filter(|_| focus.active);
+    if focus.marker.map(|(_, target)| target) == want {return;}
+    if let Some((marker, _)) = focus.marker.take() {
+        // gone already if what it was on was despawned
+        if markers.contains(marker) {
+            commands.entity(marker).despawn_recursive();
+        }
+    }
+    let color = Color::rgba(0.2, 0.9, 1.0, 0.8);
+    match want {
+        Some(Target::Board(e)) => {
+            let size = if let Ok(size) = board.get(e) {size} else {return;};
+            let marker = commands.spawn_bundle(SpriteBundle {
+                sprite: Sprite { color, custom_size: Some(size.0 * 1.25), ..Default::default() },
+                texture: icons.get("item_frame"),
+                transform: Transform::from_translation(Vec3::Z * part::FOCUS),
+                ..Default::default()
+            }).insert(FocusMarker).id();
+            commands.entity(e).add_child(marker);
+            focus.marker = Some((marker, Target::Board(e)));
+        },
+        Some(Target::Slot(e)) => {
+            if !slots.contains(e) {return;}
+            let marker = commands.spawn_bundle(ImageBundle {
+                image: icons.get("item_frame").into(),
+                color: color.into(),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: bevy::prelude::Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    ..Default::default()
+                },
+                focus_policy: bevy::ui::FocusPolicy::Pass,
+                ..Default::default()
+            }).insert(FocusMarker).id();
+            commands.entity(e).add_child(marker);
+            focus.marker = Some((marker, Target::Slot(e)));
+        },
+        None => {},
+    }
+}
+
+/// scrolls the item space so the slot with focus can be seen
+pub fn scroll_to_focus(
+    focus: Res<Focus>,
+    view: Res<BoardView>,
+    slots: Query<(&GlobalTransform, &Node), With<ItemSpaceItem>>,
+    mut item_space: Query<&mut Style, With<ItemSpace>>,
+) {
+    if !focus.is_changed() || !focus.active {return;}
+    let slot = if let Some(Target::Slot(e)) = focus.target {e} else {return;};
+    let (t, node) = if let Ok(s) = slots.get(slot) {s} else {return;};
+    let mut style = if let Ok(s) = item_space.get_single_mut() {s} else {return;};
+    let y = t.translation().y;
+    let half = node.size.y / 2.;
+    let top = view.size.y - half;
+    let bottom = view.size.y - ITEM_SPACE_SIZE.y + half;
+    let delta = if y > top {top - y} else if y < bottom {bottom - y} else {return;};
+    style.position.bottom = match style.position.bottom {
+        Val::Px(v) => Val::Px(v + delta),
+        _ => Val::Px(delta),
+    };
+}
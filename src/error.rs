@@ -18,6 +18,14 @@ pub enum GameError {
     WrongChar(char, char),
     #[error("Float Parse Err")]
     FloatErr(#[from] std::num::ParseFloatError),
+    #[error("Bad recipie expression: {0}")]
+    BadExpression(String),
+    #[error("Bad repeat policy: {0}")]
+    BadRepeat(String),
+    #[error("Script error in {0}: {1}")]
+    ScriptError(String, String),
+    #[error("Can not read save version {0}")]
+    SaveVersion(u32),
     #[error("Tag Err")]
     TagErr(#[from] crate::item::tags::TagError),
 }
\ No newline at end of file
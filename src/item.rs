@@ -1,31 +1,71 @@
 use crate::prelude::*;
 
-mod config;
+pub mod config;
 mod items;
 mod event;
 pub mod physics;
-mod pickup;
+pub mod pickup;
+pub mod spatial;
+pub mod select;
+pub mod layout;
+pub mod placement;
+pub mod history;
+pub mod layers;
 
 pub mod tags;
 
 pub use items::Items;
-pub use event::ItemEvent;
+pub use event::{ItemEvent, ItemNotice, ItemStage, ItemSystem};
 
+/// items on the board and everything that happens to them, runs without a window
+/// next to `HeadlessViewPlugin`, `UiIconsPlugin` and `SaveLoadPlugin`
 pub struct ItemPlugin;
 impl Plugin for ItemPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ItemEvent>();
+        app.add_event::<ItemNotice>();
+        app.add_stage_after(CoreStage::Update, ItemStage, SystemStage::parallel());
+        app.init_resource::<crate::AssetDir>();
+        app.init_resource::<tags::TagRegistry>();
         app.init_resource::<Items>();
         app.init_resource::<config::ItemConfig>();
-        app.add_system(spawn_item);
-        app.add_system_to_stage(CoreStage::PostUpdate, event::move_down);
+        app.add_system_to_stage(ItemStage, pickup::set_selected.label(ItemSystem::Carry));
+        app.add_system_to_stage(ItemStage, physics::detect_drop.label(ItemSystem::Drop).after(ItemSystem::Carry));
+        app.add_system_to_stage(ItemStage, physics::item_hit.label(ItemSystem::Drop).after(ItemSystem::Carry));
+        app.add_system_to_stage(ItemStage, spawn_item.label(ItemSystem::Spawn).after(ItemSystem::Combine));
+        app.add_system_to_stage(ItemStage, items::found_update.label(ItemSystem::Spawn).after(ItemSystem::Combine));
+        app.add_system_to_stage(ItemStage, despawn_item.label(ItemSystem::Spawn).after(ItemSystem::Combine));
+        app.init_resource::<layers::Layers>();
+        app.add_system_to_stage(CoreStage::PostUpdate, layers::raise);
+        app.add_system_to_stage(CoreStage::PostUpdate, layers::renormalize.after(layers::raise));
+        app.add_system_to_stage(CoreStage::PostUpdate, layers::hold);
+        app.add_system_to_stage(CoreStage::PostUpdate, layers::apply_layers.after(layers::renormalize).after(layers::hold));
         app.insert_resource(physics::Seleced(None));
-        app.add_system(physics::click_check);
-        app.add_system(physics::detect_drop);
-        app.add_system(physics::item_hit);
+        app.init_resource::<spatial::SpatialIndex>();
+        app.add_system_to_stage(CoreStage::PostUpdate, layout::relocate_lost.before(spatial::update_index));
+        app.add_system_to_stage(CoreStage::PostUpdate, spatial::update_index.after(layers::apply_layers));
+        app.init_resource::<select::Selection>();
+        app.init_resource::<pickup::DragTrail>();
+        app.init_resource::<history::History>();
+        app.add_system(select::highlight_selection);
+        app.add_system(physics::separate_items);
+        app.add_system(physics::fling);
         app.add_system(pickup::move_pickup_item);
-        app.add_system(pickup::set_selected);
-        app.add_system(items::found_update);
+        app.add_system(layout::animate_moves);
+    }
+}
+
+/// the mouse and keyboard side of items, needs `InputPlugin`
+pub struct ItemInputPlugin;
+impl Plugin for ItemInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(physics::click_check);
+        app.add_system(select::rubber_band);
+        app.add_system(select::group_actions);
+        app.add_system(layout::toggle_snap);
+        app.add_system(layout::tidy);
+        app.add_system(layout::recall);
+        app.add_system(history::undo_redo);
     }
 }
 
@@ -87,72 +127,129 @@ fn spawn_item(
     mut commands: Commands,
     icons: Res<crate::ui::UiIcons>,
     items: Res<Items>,
-    mut set: ParamSet<(EventReader<ItemEvent>, EventWriter<ItemEvent>)>,
+    registry: Res<TagRegistry>,
+    mut events: EventReader<ItemEvent>,
+    mut notices: EventWriter<ItemNotice>,
     item_settings: Res<config::ItemConfig>,
-    window: Res<WindowDescriptor>,
+    view: Res<crate::view::BoardView>,
+    index: Res<spatial::SpatialIndex>,
+    mut history: ResMut<history::History>,
 ){
     let mut send = Vec::new();
-    for event in set.p0().iter() {
+    // the index only has these next frame
+    let mut claimed = Vec::new();
+    let size = item_settings.frame_size;
+    let events: Vec<&ItemEvent> = events.iter().collect();
+    // used up to make what is being spawned so they are not in the way
+    let leaving: Vec<Entity> = events.iter().filter_map(|event| if let ItemEvent::Despawn(e) = event {Some(*e)} else {None}).collect();
+    for event in events {
         match event {
             ItemEvent::Spawn(id) => {
-                use rand::Rng;
-                let mut rng = rand::thread_rng();
-                let width = window.width / 2.;
-                let height = window.height / 2.;
-                let x = rng.gen_range(-width..(width - 200.));
-                let y = rng.gen_range((-height + 150.0)..height);
-                let s_id = commands.spawn_bundle(
-                    SpriteBundle {
-                        sprite: Sprite {custom_size: Some(item_settings.frame_size), ..Default::default()},
-                        texture: icons.get("item_frame"),
-                        transform: Transform::from_translation(Vec3{x, y, z: 0.0}),
-                        ..Default::default()
-                    }
-                )
-                .with_children(|p| {
-                    p.spawn_bundle(SpriteBundle{
-                        sprite: Sprite {custom_size: Some(item_settings.icon_size), ..Default::default()},
-                        texture: items.get(id).icon.clone(),
-                        transform: Transform::from_translation(Vec3::Z * 0.1),
-                        ..Default::default()
-                    });
-                })
-                .insert(*id)
-                .insert(physics::Size(item_settings.frame_size))
-                .id();
-                send.push(s_id);
+                // recipies only know the names items have now
+                let id = items.resolve(*id);
+                let preferred = placement::random_spot(&view, item_settings.board_size, size);
+                let area = layout::playable_area(&view, item_settings.board_size);
+                let at = placement::find_spot(preferred, size, area, &index, &claimed, &leaving);
+                claimed.push(at);
+                send.push(spawn_board_item(&mut commands, id, at.extend(0.0), &icons, &items, &registry, &item_settings));
+            },
+            ItemEvent::SpawnAt(id, loc) |
+            ItemEvent::PlayerSpawnAt(id, loc) => {
+                let id = items.resolve(*id);
+                let area = layout::area_around(loc.truncate(), &view, item_settings.board_size);
+                let at = placement::find_spot(loc.truncate(), size, area, &index, &claimed, &leaving);
+                claimed.push(at);
+                let e = spawn_board_item(&mut commands, id, at.extend(loc.z), &icons, &items, &registry, &item_settings);
+                // scripts and the story spawning things are not the player's to undo
+                if let ItemEvent::PlayerSpawnAt(..) = event {
+                    history.record(history::Change::Spawned(e, id));
+                }
+                send.push(e);
             },
-            ItemEvent::SpawnAt(id, loc) => {
-                let s_id = commands.spawn_bundle(
-                    SpriteBundle {
-                        sprite: Sprite {custom_size: Some(item_settings.frame_size), ..Default::default()},
-                        texture: icons.get("item_frame"),
-                        transform: Transform::from_translation(*loc),
-                        ..Default::default()
-                    }
-                )
-                .with_children(|p| {
-                    p.spawn_bundle(SpriteBundle{
-                        sprite: Sprite {custom_size: Some(item_settings.icon_size), ..Default::default()},
-                        texture: items.get(&id).icon.clone(),
-                        transform: Transform::from_translation(Vec3::Z * 0.1),
-                        ..Default::default()
-                    });
-                })
-                .insert(*id)
-                .insert(physics::Size(item_settings.frame_size))
-                .id();
-                send.push(s_id);
+            ItemEvent::Restore(id, at, locked) => {
+                let id = items.resolve(*id);
+                claimed.push(at.truncate());
+                let e = spawn_board_item(&mut commands, id, *at, &icons, &items, &registry, &item_settings);
+                if *locked {crate::ui::lock(&mut commands, e, item_settings.frame_size);}
+                send.push(e);
             },
             _ => {},
         }
     }
     for id in send {
-        set.p1().send(ItemEvent::Spawned(id));
-        println!("send spawned {:?}", id);
+        notices.send(ItemNotice::Spawned(id));
+    }
+}
+
+fn despawn_item(
+    mut commands: Commands,
+    mut events: EventReader<ItemEvent>,
+    items: Query<(), With<ItemID>>,
+) {
+    for event in events.iter() {
+        if let ItemEvent::Despawn(e) = event {
+            // could have been combined or despawned already this frame
+            if !items.contains(*e) {continue;}
+            commands.entity(*e).despawn_recursive();
+        }
     }
 }
 
+fn spawn_board_item(
+    commands: &mut Commands,
+    id: ItemID,
+    at: Vec3,
+    icons: &crate::ui::UiIcons,
+    items: &Items,
+    registry: &TagRegistry,
+    item_settings: &config::ItemConfig,
+) -> Entity {
+    let style = items.tags(&id).map(|tags| registry.style(tags)).unwrap_or_default();
+    commands.spawn_bundle(
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(item_settings.frame_size),
+                color: style.tint.unwrap_or(Color::WHITE),
+                ..Default::default()
+            },
+            texture: icons.get("item_frame"),
+            transform: Transform::from_translation(at),
+            ..Default::default()
+        }
+    )
+    .with_children(|p| {
+        p.spawn_bundle(SpriteBundle{
+            sprite: Sprite {custom_size: Some(item_settings.icon_size), ..Default::default()},
+            texture: items.get(&id).icon.clone(),
+            transform: Transform::from_translation(Vec3::Z * layers::part::ICON),
+            ..Default::default()
+        });
+        if let Some(glow) = style.glow {
+            p.spawn_bundle(SpriteBundle{
+                sprite: Sprite {custom_size: Some(item_settings.frame_size * 1.2), color: glow, ..Default::default()},
+                texture: icons.get("item_frame"),
+                transform: Transform::from_translation(Vec3::Z * layers::part::GLOW),
+                ..Default::default()
+            });
+        }
+        if let Some(badge) = style.badge {
+            let size = item_settings.frame_size * 0.3;
+            let corner = (item_settings.frame_size - size) / 2.;
+            p.spawn_bundle(SpriteBundle{
+                sprite: Sprite {custom_size: Some(size), ..Default::default()},
+                texture: badge,
+                transform: Transform::from_translation(corner.extend(layers::part::BADGE)),
+                ..Default::default()
+            });
+        }
+    })
+    .insert(id)
+    .insert(physics::Size(item_settings.frame_size))
+    .insert(layers::Layer::Board)
+    .insert(layers::DrawOrder::default())
+    .id()
+}
+
 impl<'a> From<&'a ItemData> for Item<'a> {
     fn from(f: &'a ItemData) -> Self {
         Item { name: &f.name, icon: &f.icon, description: &f.description, sound: &f.sound }
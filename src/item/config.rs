@@ -5,22 +5,66 @@ use serde::{Serialize, Deserialize};
 pub struct ItemConfig{
     pub frame_size: Vec2,
     pub icon_size: Vec2,
+    /// dropped items line up on a grid of `snap_size`
+    #[serde(default)]
+    pub snap: bool,
+    #[serde(default = "default_snap_size")]
+    pub snap_size: f32,
+    /// overlapping items slowly push each other apart
+    #[serde(default = "default_separate")]
+    pub separate: bool,
+    /// how many actions can be undone
+    #[serde(default = "default_history_depth")]
+    pub history_depth: usize,
+    /// while dragging onto a pair that was made before show what it made
+    #[serde(default = "default_preview_results")]
+    pub preview_results: bool,
+    /// items are kept within a box this big around the origin, the camera can pan and zoom
+    /// anywhere but the board itself is deliberately bounded so nothing gets lost for good
+    #[serde(default = "default_board_size")]
+    pub board_size: Vec2,
+}
+
+fn default_board_size() -> Vec2 {
+    Vec2::new(6400., 3600.)
+}
+
+fn default_preview_results() -> bool {
+    true
+}
+
+fn default_history_depth() -> usize {
+    50
+}
+
+fn default_separate() -> bool {
+    true
+}
+
+fn default_snap_size() -> f32 {
+    110.
 }
 
 impl FromWorld for ItemConfig {
-    fn from_world(_: &mut World) -> Self {
-        if let Ok(res) = load_item_config() {
+    fn from_world(world: &mut World) -> Self {
+        if let Ok(res) = load_item_config(&crate::AssetDir::of(world)) {
             res
         } else {
             ItemConfig {
                 frame_size: Vec2::splat(100.),
                 icon_size: Vec2::splat(90.),
+                snap: false,
+                snap_size: default_snap_size(),
+                separate: default_separate(),
+                history_depth: default_history_depth(),
+                preview_results: default_preview_results(),
+                board_size: default_board_size(),
             }
         }
     }
 }
 
-fn load_item_config() -> Result<ItemConfig, GameError> {
-    let data = std::fs::read_to_string("./assets/item.config")?;
+fn load_item_config(dir: &crate::AssetDir) -> Result<ItemConfig, GameError> {
+    let data = std::fs::read_to_string(dir.file("item.config"))?;
     Ok(ron::from_str(&data)?)
 }
\ No newline at end of file
@@ -1,34 +1,46 @@
 use crate::prelude::*;
 
+/// asks for something to happen to items, handled in `ItemStage`
 pub enum ItemEvent {
     ToolTip(ItemID),
     Spawn(ItemID),
+    /// as close to the spot as there is room
     SpawnAt(ItemID, Vec3),
-    Spawned(Entity),
+    /// as `SpawnAt` but asked for by the player, so it is undone with their action
+    PlayerSpawnAt(ItemID, Vec3),
+    /// exactly where it was and if it was locked there, for putting back a saved board
+    Restore(ItemID, Vec3, bool),
     Pickup(Entity),
     Drop,
-    Droped(Entity),
     CheckCombine(Entity, Entity),
+    /// takes an item off the board without making anything
+    Despawn(Entity),
+}
+
+/// tells everyone what happened to items after it happened
+pub enum ItemNotice {
+    Spawned(Entity),
+    Droped(Entity),
     Found(ItemID),
+    /// the pair, what it made and where
+    Combined(ItemID, ItemID, Vec<ItemID>, Vec2),
 }
 
-pub fn move_down(
-   mut h: Local<f32>,
-   mut query: Query<&mut Transform>,
-   mut events: EventReader<ItemEvent>,
-) {
-    for event in events.iter() {
-        match event {
-            ItemEvent::Pickup(e) |
-            ItemEvent::Spawned(e) => {
-                if let Ok(mut t) = query.get_mut(*e) {
-                    *h += 0.001;
-                    t.translation.z = *h;
-                } else {
-                    warn!("failed to find entity: {:?}", e);
-                }
-            },
-            _ => {},
-        }
-    }
-}
\ No newline at end of file
+/// runs right after `CoreStage::Update` so every `ItemEvent` sent during update
+/// is handled the same frame, commands are applied before `CoreStage::PostUpdate`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub struct ItemStage;
+
+/// the order items are handled in `ItemStage`, each step sees everything the steps before it sent
+/// so dropping an item on another has made its results by the end of the frame
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub enum ItemSystem {
+    /// `Pickup` and `Drop`, sends `Droped`
+    Carry,
+    /// `Droped` on top of another item, sends `CheckCombine`
+    Drop,
+    /// `CheckCombine`, sends `Despawn` for what was used, `PlayerSpawnAt` for what was made and `Combined`
+    Combine,
+    /// `Spawn`, `SpawnAt`, `PlayerSpawnAt`, `Restore` and `Despawn`, sends `Spawned` and `Found`
+    Spawn,
+}
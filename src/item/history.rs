@@ -0,0 +1,239 @@
+use std::collections::VecDeque;
+
+use crate::{prelude::*, recipies::Recipies, serde::SaveGame, ui::{ItemSpaceItem, UiIcons}};
+
+use super::{config::ItemConfig, physics::{Size, Velocity}, pickup::Grabbed, layout::MoveTo, select::{ctrl_held, shift_held}};
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use crate::item::ItemID;
+    use super::{Change, History};
+
+    #[test]
+    fn undo_then_redo() {
+        let mut history = History::new(10);
+        let e = Entity::from_raw(1);
+        history.record(Change::Moved(e, Vec2::ZERO));
+        assert!(history.undo().is_none(), "nothing is recorded before the first action");
+        history.begin();
+        history.record(Change::Moved(e, Vec2::ONE));
+        history.begin();
+        history.begin();
+        assert_eq!(history.undo(), Some(vec![Change::Moved(e, Vec2::ONE)]));
+        assert!(history.undo().is_none());
+        history.undone(vec![Change::Moved(e, Vec2::X)]);
+        assert_eq!(history.redo(), Some(vec![Change::Moved(e, Vec2::X)]));
+        history.redone(vec![Change::Moved(e, Vec2::ONE)]);
+        assert_eq!(history.undo(), Some(vec![Change::Moved(e, Vec2::ONE)]));
+    }
+
+    #[test]
+    fn new_action_clears_redo() {
+        let mut history = History::new(10);
+        history.begin();
+        history.undone(vec![Change::Found(ItemID::from("Void"))]);
+        history.record(Change::Found(ItemID::from("Space")));
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn bounded_depth() {
+        let mut history = History::new(3);
+        for i in 0..5 {
+            history.begin();
+            history.record(Change::Moved(Entity::from_raw(i), Vec2::ZERO));
+        }
+        let mut steps = 0;
+        while history.undo().is_some() {steps += 1;}
+        assert_eq!(steps, 3);
+    }
+
+    #[test]
+    fn remap_respawned() {
+        let mut history = History::new(10);
+        let old = Entity::from_raw(1);
+        let new = Entity::from_raw(2);
+        history.begin();
+        history.record(Change::Moved(old, Vec2::ZERO));
+        history.begin();
+        history.record(Change::Spawned(Entity::from_raw(3), ItemID::from("Void")));
+        history.remap(old, new);
+        history.undo();
+        assert_eq!(history.undo(), Some(vec![Change::Moved(new, Vec2::ZERO)]));
+    }
+}
+
+/// one thing a player action did, undoing it gives back its opposite to redo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    /// item was moved away from here
+    Moved(Entity, Vec2),
+    Spawned(Entity, ItemID),
+    Despawned(Entity, ItemID, Vec3),
+    /// item was found for the first time
+    Found(ItemID),
+    Lost(ItemID),
+    /// pair was combined for the first time
+    Made(ItemID, ItemID),
+    Unmade(ItemID, ItemID),
+}
+
+impl Change {
+    fn remap(&mut self, old: Entity, new: Entity) {
+        match self {
+            Change::Moved(e, _) |
+            Change::Spawned(e, _) |
+            Change::Despawned(e, _, _) => if *e == old {*e = new},
+            _ => {},
+        }
+    }
+}
+
+/// player actions that can be undone with `ctrl + Z` and redone with `ctrl + Y`
+pub struct History {
+    undo: VecDeque<Vec<Change>>,
+    redo: Vec<Vec<Change>>,
+    depth: usize,
+}
+
+impl FromWorld for History {
+    fn from_world(world: &mut World) -> Self {
+        History::new(world.resource::<ItemConfig>().history_depth)
+    }
+}
+
+impl History {
+    pub fn new(depth: usize) -> History {
+        History { undo: VecDeque::new(), redo: Vec::new(), depth: depth.max(1) }
+    }
+
+    /// starts a new player action, everything recorded after is undone with it
+    pub fn begin(&mut self) {
+        if self.undo.back().map_or(false, |step| step.is_empty()) {return;}
+        self.undo.push_back(Vec::new());
+        self.trim();
+    }
+
+    /// adds to the current action, before the first action nothing is recorded
+    /// so loading and the story setting up the board can't be undone
+    pub fn record(&mut self, change: Change) {
+        if let Some(step) = self.undo.back_mut() {
+            step.push(change);
+            self.redo.clear();
+        }
+    }
+
+    pub fn undo(&mut self) -> Option<Vec<Change>> {
+        while let Some(step) = self.undo.pop_back() {
+            if !step.is_empty() {return Some(step);}
+        }
+        None
+    }
+
+    pub fn redo(&mut self) -> Option<Vec<Change>> {
+        self.redo.pop()
+    }
+
+    /// `inverse` of an undone action
+    pub fn undone(&mut self, inverse: Vec<Change>) {
+        self.redo.push(inverse);
+        while self.redo.len() > self.depth {
+            self.redo.remove(0);
+        }
+    }
+
+    /// `inverse` of a redone action
+    pub fn redone(&mut self, inverse: Vec<Change>) {
+        self.undo.push_back(inverse);
+        self.trim();
+    }
+
+    /// an item was respawned as a new entity
+    pub fn remap(&mut self, old: Entity, new: Entity) {
+        for change in self.undo.iter_mut().flatten().chain(self.redo.iter_mut().flatten()) {
+            change.remap(old, new);
+        }
+    }
+
+    fn trim(&mut self) {
+        while self.undo.len() > self.depth {
+            self.undo.pop_front();
+        }
+    }
+}
+
+pub fn undo_redo(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut history: ResMut<History>,
+    mut items: ResMut<Items>,
+    mut recipies: ResMut<Recipies>,
+    mut board: Query<&mut Transform, (With<ItemID>, With<Size>)>,
+    item_space: Query<(Entity, &ItemID), With<ItemSpaceItem>>,
+    grabbed: Query<(), With<Grabbed>>,
+    (icons, registry, config): (Res<UiIcons>, Res<TagRegistry>, Res<ItemConfig>),
+    mut notices: EventWriter<ItemNotice>,
+    mut save: EventWriter<SaveGame>,
+) {
+    if !ctrl_held(&keys) || !grabbed.is_empty() {return;}
+    let redo = keys.just_pressed(KeyCode::Y) || (keys.just_pressed(KeyCode::Z) && shift_held(&keys));
+    if !redo && !keys.just_pressed(KeyCode::Z) {return;}
+    let step = if redo {history.redo()} else {history.undo()};
+    let mut step = if let Some(step) = step {step} else {return;};
+    // undone last to first, so undoing the inverse replays in the original order
+    let mut inverse = Vec::new();
+    while let Some(change) = step.pop() {
+        match change {
+            Change::Moved(e, from) => {
+                let mut t = if let Ok(t) = board.get_mut(e) {t} else {continue;};
+                inverse.push(Change::Moved(e, t.translation.truncate()));
+                t.translation.x = from.x;
+                t.translation.y = from.y;
+                commands.entity(e).remove::<MoveTo>().remove::<Velocity>();
+            },
+            Change::Spawned(e, id) => {
+                let t = if let Ok(t) = board.get(e) {t} else {continue;};
+                inverse.push(Change::Despawned(e, id, t.translation));
+                commands.entity(e).despawn_recursive();
+            },
+            Change::Despawned(old, id, at) => {
+                let e = super::spawn_board_item(&mut commands, id, at, &icons, &items, &registry, &config);
+                notices.send(ItemNotice::Spawned(e));
+                history.remap(old, e);
+                for change in step.iter_mut().chain(inverse.iter_mut()) {
+                    change.remap(old, e);
+                }
+                inverse.push(Change::Spawned(e, id));
+            },
+            Change::Found(id) => {
+                if items.forget(id) {
+                    for (e, _) in item_space.iter().filter(|(_, i)| **i == id) {
+                        commands.entity(e).despawn_recursive();
+                    }
+                }
+                inverse.push(Change::Lost(id));
+            },
+            Change::Lost(id) => {
+                if items.discover(id) {
+                    notices.send(ItemNotice::Found(id));
+                }
+                inverse.push(Change::Found(id));
+            },
+            Change::Made(item1, item2) => {
+                recipies.forget_made(item1, item2);
+                inverse.push(Change::Unmade(item1, item2));
+            },
+            Change::Unmade(item1, item2) => {
+                recipies.set_made(item1, item2);
+                inverse.push(Change::Made(item1, item2));
+            },
+        }
+    }
+    if redo {
+        history.redone(inverse);
+    } else {
+        history.undone(inverse);
+    }
+    save.send(SaveGame);
+}
@@ -1,32 +1,54 @@
 use crate::prelude::*;
 use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}};
 
-use super::{ItemData, Item, ItemID};
+use super::{ItemData, Item, ItemID, tags::TagRegistry};
 
-const ITEM_SAVE: &'static str = "./assets/found.sav";
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use crate::item::ItemData;
+    use super::Items;
+
+    fn test_data(name: &str) -> ItemData {
+        ItemData {
+            name: name.to_string(),
+            description: String::new(),
+            icon: Handle::default(),
+            tags: Tags::default(),
+            sound: Handle::default(),
+        }
+    }
+
+    #[test]
+    fn alias_resolves() {
+        let mut items = Items {
+            data: Default::default(),
+            debug_item: test_data("Debug Item"),
+            has_tag: Default::default(),
+            found: Default::default(),
+            aliases: Default::default(),
+        };
+        let dirt = ItemID::from("Dirt");
+        items.insert(dirt, test_data("Dirt"));
+        items.add_alias("Earth", dirt);
+        assert_eq!(items.resolve(ItemID::from("Earth")), dirt);
+        assert_eq!(items.get(&ItemID::from("Earth")).name(), "Dirt");
+        assert_eq!(items.resolve(ItemID::from("Void")), ItemID::from("Void"));
+    }
+}
 
 pub struct Items {
     data: HashMap<ItemID, ItemData>,
     debug_item: ItemData,
     has_tag: HashMap<Tag, HashSet<ItemID>>,
     found: HashSet<ItemID>,
+    aliases: HashMap<ItemID, ItemID>,
 }
 
 impl FromWorld for Items {
     fn from_world(world: &mut World) -> Self {
-        use std::fs;
-        let asset_server = world.resource::<AssetServer>();
-        let found = if let Ok(str) = fs::read_to_string(ITEM_SAVE) {
-            let mut set = HashSet::default();
-            for line in str.lines() {
-                let line = line.trim();
-                let line = if line.starts_with(']') {&line[1..].trim()} else {line};
-                let line = if line.ends_with(']') {&line[..line.len()-1].trim()} else {line};
-                if line.len() < 1 {continue;}
-                set.insert(ItemID::new(line));
-            }
-            set
-        } else {HashSet::default()};
+        let dir = crate::AssetDir::of(world);
+        let asset_server = world.resource::<AssetServer>().clone();
         let mut items = Items {
             data: HashMap::default(),
             debug_item: ItemData {
@@ -37,21 +59,24 @@ impl FromWorld for Items {
                 sound: asset_server.load("sounds/pop.wav"),
             },
             has_tag: HashMap::default(),
-            found,
+            found: HashSet::default(),
+            aliases: HashMap::default(),
         };
-        if let Err(e) = items.load_folder("./assets", &asset_server) {
-            error!("{}", e);
-        }
-        if let Err(e) = items.path_items("./assets", &asset_server) {
-            error!("{}", e);
-        }
+        world.resource_scope(|_, mut registry: Mut<TagRegistry>| {
+            if let Err(e) = items.load_folder(&dir.0, &asset_server, &mut registry) {
+                error!("{}", e);
+            }
+            if let Err(e) = items.path_items(&dir.0, &mut registry) {
+                error!("{}", e);
+            }
+        });
         items
     }
 }
 
 impl Items {
     pub fn get(&self, id: &ItemID) -> Item {
-        if let Some(data) = self.data.get(id) {
+        if let Some(data) = self.data.get(&self.resolve(*id)) {
             data.into()
         } else {
             Item {
@@ -63,77 +88,96 @@ impl Items {
         }
     }
 
+    /// false for anything that would come out as the debug item
+    pub fn contains(&self, id: &ItemID) -> bool {
+        self.data.contains_key(&self.resolve(*id))
+    }
+
+    /// maps an id from an old item name onto the item that now owns it
+    pub fn resolve(&self, id: ItemID) -> ItemID {
+        if self.data.contains_key(&id) {return id;}
+        if let Some(new) = self.aliases.get(&id) {
+            *new
+        } else {
+            id
+        }
+    }
+
+    pub fn add_alias(&mut self, alias: impl Into<ItemID>, id: ItemID) {
+        let alias: ItemID = alias.into();
+        if alias == id {return;}
+        if let Some(old) = self.aliases.insert(alias, id) {
+            if old != id {warn!("alias {:?} was for {:?} now for {:?}", alias, old, id);}
+        }
+    }
+
     pub fn found(&self) -> Vec<ItemID> {
         self.found.iter().map(|i| *i).collect()
     }
 
-    pub fn load_folder<P>(&mut self, path: P, asset_server: &AssetServer) -> Result<(), GameError> where P: AsRef<Path> {
+    /// marks `id` as found, false if it already was
+    pub fn discover(&mut self, id: ItemID) -> bool {
+        self.found.insert(id)
+    }
+
+    /// marks `id` as not found, false if it never was
+    pub fn forget(&mut self, id: ItemID) -> bool {
+        self.found.remove(&id)
+    }
+
+    pub fn load_folder<P>(&mut self, path: P, asset_server: &AssetServer, registry: &mut TagRegistry) -> Result<(), GameError> where P: AsRef<Path> {
         use std::fs;
         for file in fs::read_dir(path)? {
             let file = match file {Ok(f) => {f}, Err(e) => {error!("file: {}", e); continue;},};
             if file.metadata()?.is_dir() {
-                if let Err(e) = self.load_folder(file.path(), asset_server) {
+                if let Err(e) = self.load_folder(file.path(), asset_server, registry) {
                     error!("Rec: {}", e);
                 };
                 continue;
             }
             if let Some(ext) = file.path().extension() {if ext != "vi" {continue;}}
-            if let Err(e) = self.load(file.path(), asset_server) {error!("load: {}", e)};
+            if let Err(e) = self.load(file.path(), asset_server, registry) {error!("load: {}", e)};
         }
         Ok(())
     }
 
-    pub fn path_items<P>(&mut self, path: P, asset_server: &AssetServer) -> Result<(), GameError> where P: AsRef<Path> {
+    pub fn path_items<P>(&mut self, path: P, registry: &mut TagRegistry) -> Result<(), GameError> where P: AsRef<Path> {
         use std::fs;
         for file in fs::read_dir(path)? {
             let file = match file {Ok(f) => {f}, Err(e) => {error!("failed load file: {}", e); continue;},};
             if file.metadata()?.is_dir() {
-                if let Err(e) = self.path_items(file.path(), asset_server) {
+                if let Err(e) = self.path_items(file.path(), registry) {
                     error!("failed recursive: {}", e);
                 };
                 continue;
             }
             if let Some(ext) = file.path().extension() {if ext != "vp" {continue;}}
-            if let Err(e) = self.path(file.path()) {error!("failed path: {}", e)};
+            if let Err(e) = self.path(file.path(), registry) {error!("failed path: {}", e)};
         }
         Ok(())
     }
 
-    pub fn save_found(&self) -> Result<(), GameError> {
-        use std::fs;
-        use std::io::prelude::*;
-        let mut file = fs::OpenOptions::new().create(true).write(true).truncate(true).open(ITEM_SAVE)?;
-        writeln!(&mut file, "[")?;
-        for item in self.found.iter() {
-            let item = self.get(item);
-            writeln!(&mut file, "{}", item.name.replace(' ', "_"))?;
-        }
-        write!(&mut file, "]")?;
-        Ok(())
-    }
-
     pub fn insert(&mut self, id: impl Into<ItemID>, data: ItemData) {
         let id: ItemID = id.into();
-        for tag in data.tags.iter() {
-            if let Some(set) = self.has_tag.get_mut(tag) {
-                set.insert(id);
-            } else {
-                let mut set = HashSet::new();
-                set.insert(id);
-                self.has_tag.insert(*tag, set);
-            }
-        }
         if self.data.contains_key(&id) {return;}
+        self.index_tags(id, &data.tags);
         self.data.insert(id.into(), data);
     }
 
-    pub fn path<P>(&mut self, path: P) -> Result<(), GameError> where P: AsRef<std::path::Path> {
+    fn index_tags(&mut self, id: ItemID, tags: &Tags) {
+        for tag in tags.iter() {
+            self.has_tag.entry(*tag).or_default().insert(id);
+        }
+    }
+
+    pub fn path<P>(&mut self, path: P, registry: &mut TagRegistry) -> Result<(), GameError> where P: AsRef<std::path::Path> {
         let data = std::fs::read_to_string(&path)?;
         for path in data.split("{next}") {
             let mut segs = path.split(':');
-            let id = if let Some(name) = segs.next() {ItemID::from(name)} else {continue;};
+            let id = if let Some(name) = segs.next() {ItemID::from(name.trim())} else {continue;};
             if let Some(path) = segs.next() {
-                if let Ok(tags) = ron::from_str::<Tags>(path) {
+                if let Ok(tags) = ron::from_str::<Vec<String>>(path) {
+                    let tags = registry.tags(&tags);
                     self.add_tags(&id, &tags);
                 }
             }
@@ -144,18 +188,26 @@ impl Items {
     pub fn add_tags(&mut self,id: &ItemID, tags: &Tags) {
         if let Some(data) =  self.data.get_mut(id) {
             data.tags.merge(tags);
-        }
+        } else {return;}
+        self.index_tags(*id, tags);
     }
 
-    pub fn with_tag(&self, tag: &Tag) -> Option<std::collections::hash_set::Iter<ItemID>> {
-        if let Some(res) = self.has_tag.get(tag) {
-            Some(res.iter())
-        } else {
-            None
+    pub fn tags(&self, id: &ItemID) -> Option<&Tags> {
+        self.data.get(&self.resolve(*id)).map(|d| &d.tags)
+    }
+
+    /// every item with `tag` or any tag below it
+    pub fn with_tag(&self, tag: &Tag, registry: &TagRegistry) -> HashSet<ItemID> {
+        let mut res = HashSet::new();
+        for tag in registry.descendants(tag) {
+            if let Some(items) = self.has_tag.get(&tag) {
+                res.extend(items.iter().copied());
+            }
         }
+        res
     }
 
-    pub fn load<P>(&mut self, path: P, asset_server: &AssetServer) -> Result<(), GameError>
+    pub fn load<P>(&mut self, path: P, asset_server: &AssetServer, registry: &mut TagRegistry) -> Result<(), GameError>
     where P: Into<PathBuf>
     {
         let mut path: PathBuf = path.into();
@@ -165,23 +217,18 @@ impl Items {
         println!("{}", path.display());
         for item in data.split("{next}") {
             if item.len() < 5 {continue;}
-            let mut map: HashMap<&str, &str> = HashMap::default();
-            for seg in item.split('\n') {
-                let mut seg = seg.split(':');
-                let name = seg.next();
-                let val = seg.next();
-                if let (Some(name), Some(val)) = (name, val) {
-                    map.insert(name.trim(), val.trim());
-                } else {
-                    debug!("failed to load {:?} with {:?}; {}:{}:{}", name, val, file!(), line!(), column!());
-                }
-            }
+            let map = read_fields(item);
             let icon: Handle<Image> = if let Ok(icon_path) = unwrap_or_t::<String>(&map, "icon") {asset_server.load(path.join(icon_path))} else {self.debug_item.icon.clone()};
             let sound: Handle<AudioSource> = if let Ok(sound_path) =  unwrap_or_t::<String>(&map, "sound") {asset_server.load(path.join(sound_path))} else {self.debug_item.sound.clone()};
             let name: String = unwrap_or_t(&map, "name")?;
             let id = ItemID::from(name.as_str());
-            let tags = unwrap_or_t(&map, "tags").unwrap_or_default();
+            let tags: Vec<String> = unwrap_or_t(&map, "tags").unwrap_or_default();
+            let tags = registry.tags(&tags);
             let description = unwrap_or_t(&map, "description").unwrap_or("No description for this item;".to_string());
+            let aliases: Vec<String> = unwrap_or_t(&map, "aliases").unwrap_or_default();
+            for alias in aliases {
+                self.add_alias(alias, id);
+            }
             self.insert(id, ItemData {
                 name,
                 icon,
@@ -199,24 +246,34 @@ impl Items {
 }
 
 pub fn found_update(
-    mut set: ParamSet<(EventReader<ItemEvent>,EventWriter<ItemEvent>)>,
+    mut events: EventReader<ItemEvent>,
+    mut notices: EventWriter<ItemNotice>,
     mut items: ResMut<Items>,
+    mut history: ResMut<super::history::History>,
 ) {
     let mut found = Vec::new();
-    for event in set.p0().iter() {
+    for event in events.iter() {
         match event {
             ItemEvent::Spawn(id) |
-            ItemEvent::SpawnAt(id, _) => {
-                if !items.found.contains(id) {
-                    items.found.insert(*id);
-                    found.push(*id);
+            ItemEvent::SpawnAt(id, _) |
+            ItemEvent::Restore(id, ..) => {
+                let id = items.resolve(*id);
+                if items.discover(id) {
+                    found.push(id);
+                }
+            }
+            ItemEvent::PlayerSpawnAt(id, _) => {
+                let id = items.resolve(*id);
+                if items.discover(id) {
+                    history.record(super::history::Change::Found(id));
+                    found.push(id);
                 }
             }
             _ => {},
         }
     }
     for found in found {
-        set.p1().send(ItemEvent::Found(found));
+        notices.send(ItemNotice::Found(found));
     }
 }
 
@@ -228,7 +285,22 @@ fn unwrap_or_404<'de, 'a>(map: &'de HashMap<&str, &'a str>, field: &str) -> Resu
     }
 }
 
-fn unwrap_or_t<'de, T>(map: &'de HashMap<&str, &str>, field: &str) -> Result<T, GameError>
+pub(super) fn read_fields(entry: &str) -> HashMap<&str, &str> {
+    let mut map: HashMap<&str, &str> = HashMap::default();
+    for seg in entry.split('\n') {
+        let mut seg = seg.split(':');
+        let name = seg.next();
+        let val = seg.next();
+        if let (Some(name), Some(val)) = (name, val) {
+            map.insert(name.trim(), val.trim());
+        } else {
+            debug!("failed to load {:?} with {:?}; {}:{}:{}", name, val, file!(), line!(), column!());
+        }
+    }
+    map
+}
+
+pub(super) fn unwrap_or_t<'de, T>(map: &'de HashMap<&str, &str>, field: &str) -> Result<T, GameError>
     where T: serde::Deserialize<'de>,
 {
     if let Some(v) = map.get(field) {
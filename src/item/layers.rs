@@ -0,0 +1,179 @@
+use crate::prelude::*;
+
+use super::pickup::Grabbed;
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use super::{compact, Layer, STEP, part};
+
+    #[test]
+    fn layer_z() {
+        assert_eq!(Layer::Board.z(0), Layer::Board.base());
+        assert_eq!(Layer::Board.z(10), Layer::Board.base() + 10. * STEP);
+        assert!(Layer::Board.z(u32::MAX) < Layer::Held.base(), "a layer never reaches the next");
+        assert!(Layer::Held.z(u32::MAX) < Layer::Effects.base());
+        assert!(Layer::Background.z(u32::MAX) < Layer::Board.base());
+    }
+
+    #[test]
+    fn parts_stay_between_neighbours() {
+        let parts = [part::PREVIEW, part::FOCUS, part::HIGHLIGHT, part::GLOW, part::ICON, part::BADGE, part::GHOST];
+        let lowest = parts.iter().copied().fold(f32::MAX, f32::min);
+        let highest = parts.iter().copied().fold(f32::MIN, f32::max);
+        assert!(highest - lowest < STEP, "an item's parts would mix with the items next to it");
+        // still apart from the item itself at the top of the board
+        let top = Layer::Board.z(u32::MAX);
+        for z in parts {
+            assert_ne!(top + z, top);
+        }
+    }
+
+    #[test]
+    fn compacting() {
+        let (a, b, c) = (Entity::from_raw(0), Entity::from_raw(1), Entity::from_raw(2));
+        let orders = compact(vec![(a, 900), (b, 12), (c, 5000)]);
+        assert_eq!(orders, vec![(b, 0), (a, 1), (c, 2)]);
+    }
+}
+
+/// z distance between two items next to each other in a layer
+const STEP: f32 = 0.01;
+/// free orders allowed to build up before the order is compacted
+const SLACK: usize = 256;
+
+/// z of the sprites making up an item relative to the item, all inside one `STEP`
+/// so an item's icon is never drawn over the item on top of it
+pub mod part {
+    use super::STEP;
+
+    /// outline showing what dropping the held item would do, under everything else
+    pub const PREVIEW: f32 = -0.4 * STEP;
+    /// outline on what the keyboard or gamepad has focus on
+    pub const FOCUS: f32 = -0.3 * STEP;
+    /// selection outline
+    pub const HIGHLIGHT: f32 = -0.2 * STEP;
+    pub const GLOW: f32 = -0.1 * STEP;
+    pub const ICON: f32 = 0.1 * STEP;
+    /// tag and lock badges, over the icon
+    pub const BADGE: f32 = 0.2 * STEP;
+    /// faded results of a preview, over everything else
+    pub const GHOST: f32 = 0.3 * STEP;
+}
+
+/// which band of z a sprite is drawn in, higher layers are always on top
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Background,
+    Board,
+    /// items being carried
+    Held,
+    /// notices and other things drawn over everything
+    Effects,
+}
+
+impl Layer {
+    /// lowest z in the layer
+    pub fn base(&self) -> f32 {
+        match self {
+            Layer::Background => 0.,
+            Layer::Board => 1.,
+            Layer::Held => 700.,
+            Layer::Effects => 800.,
+        }
+    }
+
+    /// how much z the layer has, orders past it all share the top
+    fn depth(&self) -> f32 {
+        match self {
+            Layer::Background => 0.9,
+            Layer::Board => 690.,
+            Layer::Held |
+            Layer::Effects => 90.,
+        }
+    }
+
+    pub fn z(&self, order: u32) -> f32 {
+        self.base() + (order as f32 * STEP).min(self.depth())
+    }
+}
+
+/// where an item is in the draw order of its layer, bigger is on top
+#[derive(Debug, Component, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DrawOrder(pub u32);
+
+/// hands out draw orders so the last touched item is on top
+#[derive(Debug, Default)]
+pub struct Layers {
+    next: u32,
+}
+
+impl Layers {
+    pub fn raise(&mut self) -> DrawOrder {
+        self.next += 1;
+        DrawOrder(self.next)
+    }
+}
+
+/// the same order with no gaps starting from 0
+fn compact(mut orders: Vec<(Entity, u32)>) -> Vec<(Entity, u32)> {
+    orders.sort_by_key(|(_, order)| *order);
+    orders.into_iter().enumerate().map(|(i, (e, _))| (e, i as u32)).collect()
+}
+
+/// picked up and spawned items go on top
+pub fn raise(
+    mut layers: ResMut<Layers>,
+    mut events: EventReader<ItemEvent>,
+    mut notices: EventReader<ItemNotice>,
+    mut orders: Query<&mut DrawOrder>,
+) {
+    let picked = events.iter().filter_map(|event| if let ItemEvent::Pickup(e) = event {Some(*e)} else {None});
+    let spawned = notices.iter().filter_map(|notice| if let ItemNotice::Spawned(e) = notice {Some(*e)} else {None});
+    for e in picked.chain(spawned) {
+        if let Ok(mut order) = orders.get_mut(e) {
+            *order = layers.raise();
+        } else {
+            warn!("failed to find entity: {:?}", e);
+        }
+    }
+}
+
+/// closes the gaps left by raising and despawning so z stays small however long the game runs
+pub fn renormalize(
+    mut layers: ResMut<Layers>,
+    mut orders: Query<(Entity, &mut DrawOrder)>,
+) {
+    let count = orders.iter().count();
+    if (layers.next as usize) <= count * 2 + SLACK {return;}
+    let compacted = compact(orders.iter().map(|(e, order)| (e, order.0)).collect());
+    for (e, order) in compacted {
+        if let Ok((_, mut o)) = orders.get_mut(e) {
+            // only touch what moved so the spatial index isn't rebuilt for nothing
+            if o.0 != order {o.0 = order;}
+        }
+    }
+    layers.next = count as u32;
+}
+
+/// carried items are drawn over the board
+pub fn hold(
+    mut items: Query<&mut Layer>,
+    grabbed: Query<Entity, Added<Grabbed>>,
+    dropped: RemovedComponents<Grabbed>,
+) {
+    for e in grabbed.iter() {
+        if let Ok(mut layer) = items.get_mut(e) {*layer = Layer::Held;}
+    }
+    for e in dropped.iter() {
+        if let Ok(mut layer) = items.get_mut(e) {*layer = Layer::Board;}
+    }
+}
+
+pub fn apply_layers(
+    mut items: Query<(&Layer, &DrawOrder, &mut Transform), Or<(Changed<Layer>, Changed<DrawOrder>)>>,
+) {
+    for (layer, order, mut t) in items.iter_mut() {
+        t.translation.z = layer.z(order.0);
+    }
+}
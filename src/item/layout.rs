@@ -0,0 +1,285 @@
+use crate::{prelude::*, view::BoardView, serde::SaveGame, ui::{ITEM_SPACE_SIZE, TOOLTIP_HEIGHT}};
+
+use super::{config::ItemConfig, pickup::Grabbed, history::{History, Change}, placement::find_spot, spatial::SpatialIndex};
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use crate::view::BoardView;
+    use super::{grid_positions, snap, clamp_to, playable_area, keep_on_board};
+
+    #[test]
+    fn snapping() {
+        assert_eq!(snap(Vec2::new(54., -54.), 110.), Vec2::new(0., -0.));
+        assert_eq!(snap(Vec2::new(56., 170.), 110.), Vec2::new(110., 220.));
+        assert_eq!(snap(Vec2::new(56., 170.), 0.), Vec2::new(56., 170.));
+    }
+
+    #[test]
+    fn clamping() {
+        let area = (Vec2::new(-100., -100.), Vec2::new(100., 100.));
+        assert_eq!(clamp_to(Vec2::new(0., 0.), Vec2::splat(20.), area), Vec2::new(0., 0.));
+        assert_eq!(clamp_to(Vec2::new(500., -95.), Vec2::splat(20.), area), Vec2::new(90., -90.));
+        // too big to fit sits against the min corner
+        assert_eq!(clamp_to(Vec2::ZERO, Vec2::splat(300.), area), Vec2::new(50., 50.));
+    }
+
+    #[test]
+    fn playable() {
+        let view = BoardView { size: Vec2::new(1280., 720.), center: Vec2::ZERO, scale: 1. };
+        let board = Vec2::splat(10000.);
+        assert_eq!(playable_area(&view, board), (Vec2::new(-640., -210.), Vec2::new(420., 360.)));
+        // the edge of the board cuts off the view
+        let view = BoardView { center: Vec2::new(4800., 0.), ..view };
+        assert_eq!(playable_area(&view, board), (Vec2::new(4160., -210.), Vec2::new(5000., 360.)));
+        // under the tool tip bar is pushed out, off screen only has to be on the board
+        let view = BoardView { center: Vec2::ZERO, ..view };
+        let size = Vec2::splat(100.);
+        assert_eq!(keep_on_board(Vec2::new(0., -300.), size, &view, board), Vec2::new(0., -160.));
+        assert_eq!(keep_on_board(Vec2::new(2000., 0.), size, &view, board), Vec2::new(2000., 0.));
+        assert_eq!(keep_on_board(Vec2::new(9000., 0.), size, &view, board), Vec2::new(4950., 0.));
+    }
+
+    #[test]
+    fn grid() {
+        let at = grid_positions(&[3, 2], Vec2::new(0., 300.), Vec2::new(250., 0.), 100., -50.);
+        assert_eq!(at, vec![
+            Vec2::new(50., 250.), Vec2::new(150., 250.),
+            Vec2::new(50., 150.),
+            Vec2::new(50., 50.), Vec2::new(150., 50.),
+        ]);
+        // more than fit in the view carry on below it, never on top of each other
+        let at = grid_positions(&[20], Vec2::new(0., 300.), Vec2::new(250., 0.), 100., -50.);
+        assert_eq!(at.len(), 20);
+        assert_eq!(at.iter().filter(|at| at.y > 0.).count(), 6);
+        assert!(at.iter().filter(|at| at.y < 0.).all(|at| at.y < -50.));
+        for (i, a) in at.iter().enumerate() {
+            assert!(at[i + 1..].iter().all(|b| (*a - *b).abs().max_element() >= 100.));
+        }
+    }
+}
+
+/// slides an item to a new spot instead of jumping there
+#[derive(Debug, Component, Clone, Copy)]
+pub struct MoveTo(pub Vec2);
+
+/// the part of the view items can be put in, the window minus the ui and never off the board
+pub fn playable_area(view: &BoardView, board_size: Vec2) -> (Vec2, Vec2) {
+    let min = view.to_world(Vec2::new(0., TOOLTIP_HEIGHT));
+    let max = view.to_world(Vec2::new(view.size.x - ITEM_SPACE_SIZE.x, view.size.y));
+    let (board_min, board_max) = board_area(board_size);
+    let (cut_min, cut_max) = (min.max(board_min), max.min(board_max));
+    // looking at nothing but empty space, the view is all there is
+    if cut_min.cmpgt(cut_max).any() {(min, max)} else {(cut_min, cut_max)}
+}
+
+pub fn board_area(board_size: Vec2) -> (Vec2, Vec2) {
+    (-board_size / 2., board_size / 2.)
+}
+
+/// moves a `size` item at `at` the least it can to be inside `area`
+pub fn clamp_to(at: Vec2, size: Vec2, (min, max): (Vec2, Vec2)) -> Vec2 {
+    let low = min + size / 2.;
+    let high = (max - size / 2.).max(low);
+    at.clamp(low, high)
+}
+
+/// an item in view can't be under the ui, anywhere else just has to be on the board
+pub fn keep_on_board(at: Vec2, size: Vec2, view: &BoardView, board_size: Vec2) -> Vec2 {
+    clamp_to(at, size, area_around(at, view, board_size))
+}
+
+/// the playable area when `at` is in view, otherwise the whole board
+pub fn area_around(at: Vec2, view: &BoardView, board_size: Vec2) -> (Vec2, Vec2) {
+    let screen = view.to_screen(at);
+    if screen.cmpge(Vec2::ZERO).all() && screen.cmple(view.size).all() {
+        playable_area(view, board_size)
+    } else {
+        board_area(board_size)
+    }
+}
+
+/// on the board but not covered by the ui
+pub fn in_area(at: Vec2, (min, max): (Vec2, Vec2)) -> bool {
+    at.cmpge(min).all() && at.cmple(max).all()
+}
+
+pub fn snap(at: Vec2, size: f32) -> Vec2 {
+    if size <= 0. {return at;}
+    (at / size).round() * size
+}
+
+/// lays out groups of `groups[i]` items in rows of `cell` squares filling
+/// `top_left..bottom_right` from the top, every group starts a new row.
+/// rows that don't fit above `bottom_right` carry on down from `overflow`,
+/// cells are never smaller than `cell` so items are never put on top of each other
+fn grid_positions(groups: &[usize], top_left: Vec2, bottom_right: Vec2, cell: f32, overflow: f32) -> Vec<Vec2> {
+    let columns = (((bottom_right.x - top_left.x) / cell).floor() as usize).max(1);
+    let fit = ((top_left.y - bottom_right.y) / cell).floor().max(0.) as usize;
+    let row_y = |row: usize| if row < fit {
+        top_left.y - (row as f32 + 0.5) * cell
+    } else {
+        overflow - ((row - fit) as f32 + 0.5) * cell
+    };
+    let mut row = 0;
+    let mut res = Vec::new();
+    for count in groups {
+        for i in 0..*count {
+            if i > 0 && i % columns == 0 {row += 1;}
+            let column = i % columns;
+            res.push(Vec2::new(top_left.x + (column as f32 + 0.5) * cell, row_y(row)));
+        }
+        if *count > 0 {row += 1;}
+    }
+    res
+}
+
+pub fn toggle_snap(
+    keys: Res<Input<KeyCode>>,
+    mut config: ResMut<ItemConfig>,
+) {
+    if keys.just_pressed(KeyCode::G) && !super::select::ctrl_held(&keys) {
+        config.snap = !config.snap;
+        info!("snap to grid: {}", config.snap);
+    }
+}
+
+/// `T` tidies the board alphabetically, `shift + T` groups by tag first
+pub fn tidy(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    view: Res<BoardView>,
+    config: Res<ItemConfig>,
+    items: Res<Items>,
+    registry: Res<TagRegistry>,
+    board: Query<(Entity, &ItemID, &Transform), (With<super::physics::Size>, Without<Grabbed>, Without<super::physics::Locked>)>,
+    mut history: ResMut<History>,
+) {
+    if !keys.just_pressed(KeyCode::T) || super::select::ctrl_held(&keys) {return;}
+    history.begin();
+    let by_tag = super::select::shift_held(&keys);
+    let mut sorted: Vec<(String, String, Entity)> = board.iter().map(|(e, id, t)| {
+        history.record(Change::Moved(e, t.translation.truncate()));
+        let group = if by_tag {
+            items.tags(id)
+                .and_then(|tags| tags.iter().map(|t| registry.name(t)).min())
+                .unwrap_or_else(|| "~".to_string())
+        } else {
+            String::new()
+        };
+        (group, items.get(id).name().to_lowercase(), e)
+    }).collect();
+    sorted.sort();
+    let mut groups: Vec<usize> = Vec::new();
+    for (i, (group, _, _)) in sorted.iter().enumerate() {
+        if i == 0 || sorted[i - 1].0 != *group {
+            groups.push(0);
+        }
+        if let Some(last) = groups.last_mut() {*last += 1;}
+    }
+    let top_left = view.to_world(Vec2::new(0., view.size.y));
+    let bottom_right = view.to_world(Vec2::new(view.size.x - crate::ui::ITEM_SPACE_SIZE.x, crate::ui::TOOLTIP_HEIGHT));
+    let cell = config.snap_size.max(config.frame_size.x.max(config.frame_size.y));
+    // the board pans, so what doesn't fit goes below the window instead of under the tool tip bar
+    let below = view.to_world(Vec2::ZERO).y;
+    let positions = grid_positions(&groups, top_left, bottom_right, cell, below);
+    for ((_, _, e), at) in sorted.iter().zip(positions) {
+        commands.entity(*e).insert(MoveTo(at));
+    }
+}
+
+/// `R` brings every item that is out of view or under the ui back onto the board
+pub fn recall(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    view: Res<BoardView>,
+    config: Res<ItemConfig>,
+    board: Query<(Entity, &Transform), (With<super::physics::Size>, Without<Grabbed>)>,
+    index: Res<SpatialIndex>,
+    mut history: ResMut<History>,
+) {
+    if !keys.just_pressed(KeyCode::R) || super::select::ctrl_held(&keys) {return;}
+    let area = playable_area(&view, config.board_size);
+    let lost: Vec<(Entity, Vec2)> = board.iter()
+        .map(|(e, t)| (e, t.translation.truncate()))
+        .filter(|(_, at)| !at.is_finite() || !in_area(*at, area))
+        .collect();
+    if lost.is_empty() {return;}
+    history.begin();
+    let (min, max) = area;
+    let cell = config.snap_size.max(config.frame_size.x.max(config.frame_size.y));
+    let positions = grid_positions(&[lost.len()], Vec2::new(min.x, max.y), Vec2::new(max.x, min.y), cell, min.y);
+    // around what is already there instead of on top of it
+    let mut claimed = Vec::new();
+    for ((e, from), preferred) in lost.into_iter().zip(positions) {
+        if from.is_finite() {
+            history.record(Change::Moved(e, from));
+        }
+        let to = find_spot(preferred, config.frame_size, area, &index, &claimed, &[]);
+        claimed.push(to);
+        commands.entity(e).insert(MoveTo(to));
+    }
+}
+
+/// items put back somewhere off the board, like a save from a bigger board, are moved onto it
+/// as close to where they were as there is room, or into view if they were nowhere
+pub fn relocate_lost(
+    mut notices: EventReader<ItemNotice>,
+    mut board: Query<&mut Transform, With<super::physics::Size>>,
+    view: Res<BoardView>,
+    config: Res<ItemConfig>,
+    index: Res<SpatialIndex>,
+) {
+    let area = board_area(config.board_size);
+    let spawned: Vec<(Entity, Vec2)> = notices.iter()
+        .filter_map(|notice| if let ItemNotice::Spawned(e) = notice {Some(*e)} else {None})
+        .filter_map(|e| board.get(e).ok().map(|t| (e, t.translation.truncate())))
+        .collect();
+    // the index only has these next frame
+    let mut claimed: Vec<Vec2> = spawned.iter().map(|(_, at)| *at).filter(|at| at.is_finite() && in_area(*at, area)).collect();
+    for (e, at) in spawned {
+        if at.is_finite() && in_area(at, area) {continue;}
+        let (preferred, within) = if at.is_finite() {
+            (clamp_to(at, config.frame_size, area), area)
+        } else {
+            let (min, max) = playable_area(&view, config.board_size);
+            ((min + max) / 2., (min, max))
+        };
+        let to = find_spot(preferred, config.frame_size, within, &index, &claimed, &[]);
+        claimed.push(to);
+        if let Ok(mut t) = board.get_mut(e) {
+            t.translation.x = to.x;
+            t.translation.y = to.y;
+        }
+    }
+}
+
+pub fn animate_moves(
+    mut commands: Commands,
+    mut moving: Query<(Entity, &mut Transform, &MoveTo), Without<Grabbed>>,
+    time: Res<Time>,
+    mut save: EventWriter<SaveGame>,
+) {
+    let step = (time.delta_seconds() * 10.).min(1.);
+    let mut any = false;
+    let mut finished = false;
+    for (e, mut transform, target) in moving.iter_mut() {
+        let at = transform.translation.truncate();
+        // something lost at infinity can't slide back
+        let next = if at.is_finite() {at.lerp(target.0, step)} else {target.0};
+        if next.distance(target.0) < 0.5 {
+            transform.translation.x = target.0.x;
+            transform.translation.y = target.0.y;
+            commands.entity(e).remove::<MoveTo>();
+            finished = true;
+        } else {
+            transform.translation.x = next.x;
+            transform.translation.y = next.y;
+            any = true;
+        }
+    }
+    // save once everything has landed
+    if finished && !any {
+        save.send(SaveGame);
+    }
+}
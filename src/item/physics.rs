@@ -1,95 +1,75 @@
-use std::cmp::Ordering;
+use crate::{prelude::*, view::BoardView};
 
-use crate::prelude::*;
+use super::{spatial::SpatialIndex, select::{Selection, shift_held}, pickup::Grabbed, layout::MoveTo, config::ItemConfig};
 
 #[derive(Debug, Component, Deref, DerefMut, PartialEq, Clone, Copy)]
 pub struct Size(pub Vec2);
 
+/// a locked item can't be picked up or pushed around
+#[derive(Debug, Component, Clone, Copy)]
+pub struct Locked;
+
 pub fn click_check(
-    query: Query<(Entity, &Transform, &Size), With<ItemID>>,
-    windows: Res<Windows>,
-    camera: Query<(&Camera, &GlobalTransform), With<crate::MainCam>>,
-    input: Res<Input<MouseButton>>,
+    index: Res<SpatialIndex>,
+    cursor: Res<WorldCursor>,
+    keys: Res<Input<KeyCode>>,
+    mut selection: ResMut<Selection>,
     mut events: EventWriter<ItemEvent>,
+    ui: Query<&Interaction>,
 ){
-    let (camera, global_transform) = camera.single();
-
-    let window = windows.get_primary().unwrap();
-
-    if let Some(screen_pos) = window.cursor_position() {
-        let window_size = Vec2::new(window.width() as f32, window.height() as f32);
-
-        let ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
-
-        let ndc_to_world = global_transform.compute_matrix() * camera.projection_matrix().inverse();
-
-        let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
-
-        let world_pos: Vec2 = world_pos.truncate();
-
-        if input.just_pressed(MouseButton::Left) {
-            let mut hits: Vec<(Entity, f32)> = Vec::new();
-            for (e, transform, size) in query.iter() {
-                // println!("Click At {}:{}", world_pos.x, world_pos.y);
-                // println!("Entity At {}:{}", transform.translation.x, transform.translation.y);
-                if box_point_hit(size.0, transform.translation.truncate(), world_pos) {
-                    hits.push((e, transform.translation.z));
+    if let Some(world_pos) = cursor.world {
+        if cursor.just_pressed && !crate::ui::pointer_on_ui(&ui) {
+            if let Some(hit) = index.top_at_point(world_pos) {
+                debug!("hit entity: {:?}", hit);
+                if shift_held(&keys) {
+                    if !selection.remove(&hit) {
+                        selection.insert(hit);
+                    }
+                } else {
+                    if !selection.contains(&hit) {
+                        selection.clear();
+                        selection.insert(hit);
+                    }
+                    events.send(ItemEvent::Pickup(hit));
                 }
             }
-            if hits.len() != 0 {
-                hits.sort_by(|(_, a),(_, b)| b.partial_cmp(a).unwrap_or(Ordering::Greater));
-                debug!("hit entity: {:?}", hits[0].0);
-                events.send(ItemEvent::Pickup(hits[0].0));
-            }
         }
-        if input.just_released(MouseButton::Left) {
+        if cursor.just_released {
             events.send(ItemEvent::Drop);
         }
     }
 }
 
 pub fn detect_drop(
-    mut set: ParamSet<(EventReader<ItemEvent>, EventWriter<ItemEvent>)>,
-    query: Query<(Entity, &Transform, &Size), With<ItemID>>,
+    mut notices: EventReader<ItemNotice>,
+    mut events: EventWriter<ItemEvent>,
+    query: Query<(&Transform, &Size), With<ItemID>>,
+    index: Res<SpatialIndex>,
 ) {
-    let mut send = Vec::new();
-    for event in set.p0().iter() {
-        if let ItemEvent::Droped(e) = event {
-            let (e, t, s): (Entity, &Transform, &Size) = if let Ok(e) = query.get(*e) {e} else {
+    for notice in notices.iter() {
+        if let ItemNotice::Droped(e) = notice {
+            let (t, s): (&Transform, &Size) = if let Ok(e) = query.get(*e) {e} else {
                 warn!("dropped {:?} that does not exist", e);
                 continue;};
-            let mut hits = Vec::new();
-            for (o_e, o_t, o_s) in query.iter() {
-                if o_e == e {continue;};
-                if box_box_hit(s.0, t.translation.truncate(), o_s.0, o_t.translation.truncate()) {
-                    hits.push((o_e, box_box_overlap(s.0, t.translation.truncate(), o_s.0, o_t.translation.truncate())))
-                }
-            }
+            let hits = index.overlapping(t.translation.truncate(), s.0, Some(*e));
             if hits.len() == 0 {continue;}
-            hits.sort_by(|a,b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Greater));
-            send.push(ItemEvent::CheckCombine(e, hits[0].0));
+            events.send(ItemEvent::CheckCombine(*e, hits[0].0));
         }
     }
-    for event in send {
-        set.p1().send(event);
-    }
 }
 
 pub fn item_hit(
-   mut events: EventReader<ItemEvent>,
-   transforms: Query<(Entity, &Transform, &Size), With<ItemID>>,
+   mut notices: EventReader<ItemNotice>,
+   transforms: Query<(&Transform, &Size), With<ItemID>>,
+   index: Res<SpatialIndex>,
 ) {
     let mut hits = Vec::new();
-    for event in events.iter() {
-        if let ItemEvent::Droped(e) = event {
-            let (e, transform, size): (Entity, &Transform, &Size) = if let Ok(t) = transforms.get(*e) {t} else {continue;};
-            for (other, other_transform, other_size) in transforms.iter() {
-                //skip self
-                if e == other {continue;}
-                if box_box_hit(size.0, transform.translation.truncate(), other_size.0, other_transform.translation.truncate()) {
-                    let over_lap = box_box_overlap(size.0, transform.translation.truncate(), other_size.0, other_transform.translation.truncate());
-                    hits.push((e, other, over_lap));
-                }
+    for notice in notices.iter() {
+        if let ItemNotice::Droped(e) = notice {
+            let (transform, size): (&Transform, &Size) = if let Ok(t) = transforms.get(*e) {t} else {continue;};
+            //skip self
+            for (other, over_lap) in index.overlapping(transform.translation.truncate(), size.0, Some(*e)) {
+                hits.push((*e, other, over_lap));
             }
         }
     }
@@ -98,6 +78,96 @@ pub fn item_hit(
     }
 }
 
+/// on a flung item, pixels a second
+#[derive(Debug, Component, Deref, DerefMut, Clone, Copy)]
+pub struct Velocity(pub Vec2);
+
+/// fraction of speed kept after a second of sliding
+const FRICTION: f32 = 0.05;
+/// fraction of speed kept after hitting the edge of the board
+const BOUNCE: f32 = 0.6;
+/// flung items slower than this have landed
+const REST_SPEED: f32 = 20.;
+
+pub fn fling(
+    mut commands: Commands,
+    mut flying: Query<(Entity, &mut Transform, &mut Velocity, &Size), Without<Grabbed>>,
+    moving: Query<(), With<Velocity>>,
+    index: Res<SpatialIndex>,
+    view: Res<BoardView>,
+    config: Res<ItemConfig>,
+    time: Res<Time>,
+    mut notices: EventWriter<ItemNotice>,
+) {
+    let dt = time.delta_seconds();
+    // bounces off the edge of the window and the ui
+    let (min, max) = super::layout::playable_area(&view, config.board_size);
+    for (e, mut transform, mut velocity, size) in flying.iter_mut() {
+        let half = size.0 / 2.;
+        let mut at = transform.translation.truncate() + velocity.0 * dt;
+        if at.x - half.x < min.x || at.x + half.x > max.x {
+            velocity.x = -velocity.x * BOUNCE;
+            at.x = at.x.clamp(min.x + half.x, (max.x - half.x).max(min.x + half.x));
+        }
+        if at.y - half.y < min.y || at.y + half.y > max.y {
+            velocity.y = -velocity.y * BOUNCE;
+            at.y = at.y.clamp(min.y + half.y, (max.y - half.y).max(min.y + half.y));
+        }
+        transform.translation.x = at.x;
+        transform.translation.y = at.y;
+        velocity.0 *= FRICTION.powf(dt);
+        // hitting something lands it so it gets the same combine check as a drop
+        let hit = index.overlapping(at, size.0, Some(e)).iter().any(|(other, _)| !moving.contains(*other));
+        if hit || velocity.length() < REST_SPEED {
+            commands.entity(e).remove::<Velocity>();
+            notices.send(ItemNotice::Droped(e));
+        }
+    }
+}
+
+/// how fast overlapping items slide apart in pixels a second
+const SEPARATION_SPEED: f32 = 300.;
+
+pub fn separate_items(
+    mut items: Query<(Entity, &mut Transform, &Size), (With<ItemID>, Without<Grabbed>, Without<MoveTo>, Without<Velocity>)>,
+    held: Query<(), With<Grabbed>>,
+    locked: Query<(), With<Locked>>,
+    index: Res<SpatialIndex>,
+    config: Res<ItemConfig>,
+    time: Res<Time>,
+) {
+    if !config.separate {return;}
+    let step = SEPARATION_SPEED * time.delta_seconds();
+    let mut pushes = Vec::new();
+    for (e, transform, size) in items.iter() {
+        if locked.contains(e) {continue;}
+        let center = transform.translation.truncate();
+        let mut push = Vec2::ZERO;
+        for (other, _) in index.overlapping(center, size.0, Some(e)) {
+            // dragging onto something is how you combine, leave those alone
+            if held.contains(other) {continue;}
+            let (other_center, other_size) = if let Ok((_, t, s)) = items.get(other) {(t.translation.truncate(), s.0)} else {continue;};
+            let overlap = box_box_overlap(size.0, center, other_size, other_center);
+            if overlap <= 0. {continue;}
+            let away = (center - other_center).try_normalize().unwrap_or_else(|| {
+                // stacked exactly, spread them out by entity so they pick different ways
+                let angle = e.id() as f32 * 2.399_963;
+                Vec2::new(angle.cos(), angle.sin())
+            });
+            push += away * overlap.sqrt();
+        }
+        if push != Vec2::ZERO {
+            pushes.push((e, push.clamp_length_max(step)));
+        }
+    }
+    for (e, push) in pushes {
+        if let Ok((_, mut transform, _)) = items.get_mut(e) {
+            transform.translation.x += push.x;
+            transform.translation.y += push.y;
+        }
+    }
+}
+
 pub fn box_point_hit(
     size: Vec2,
     center: Vec2,
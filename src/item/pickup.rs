@@ -1,37 +1,126 @@
-use bevy::input::mouse::MouseMotion;
-
 pub use crate::prelude::*;
+use crate::view::BoardView;
+
+use std::collections::{HashMap, VecDeque};
+
+use super::{physics::{Seleced, Velocity, Locked}, select::Selection, config::ItemConfig, layout::{snap, clamp_to, playable_area}, history::{History, Change}};
+
+/// on an item being dragged, how far the item center is from the cursor
+#[derive(Debug, Component, Deref, Clone, Copy)]
+pub struct Grabbed(pub Vec2);
+
+/// how long the cursor trail used for release velocity is in seconds
+const TRAIL_LENGTH: f64 = 0.1;
+/// slower than this a release is just a drop
+const FLING_SPEED: f32 = 250.;
+
+/// where the cursor has been over the last `TRAIL_LENGTH` of a drag
+#[derive(Debug, Default)]
+pub struct DragTrail(VecDeque<(f64, Vec2)>);
 
-use super::physics::Seleced;
+impl DragTrail {
+    fn push(&mut self, time: f64, at: Vec2) {
+        self.0.push_back((time, at));
+        while self.0.len() > 2 && self.0.front().map_or(false, |(t, _)| time - t > TRAIL_LENGTH) {
+            self.0.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn velocity(&self) -> Vec2 {
+        match (self.0.front(), self.0.back()) {
+            (Some((t0, a)), Some((t1, b))) if t1 > t0 => (*b - *a) / (t1 - t0) as f32,
+            _ => Vec2::ZERO,
+        }
+    }
+}
 
 pub fn move_pickup_item(
-    res: Res<super::physics::Seleced>,
-    mut transforms: Query<&mut Transform, With<ItemID>>,
-    mut events: EventReader<MouseMotion>,
+    cursor: Res<WorldCursor>,
+    mut transforms: Query<(&mut Transform, &Grabbed, &Size), With<ItemID>>,
+    mut trail: ResMut<DragTrail>,
+    time: Res<Time>,
+    view: Res<BoardView>,
+    config: Res<ItemConfig>,
 ) {
-    let e = if let Some(e) = res.0 {e} else {return;};
-    let mut transform = if let Ok(t) =  transforms.get_mut(e) {t} else {return;};
-    let mut delta = Vec2::ZERO;
-    for event in events.iter() {
-        delta += event.delta;
+    let cursor = if let Some(c) = cursor.world {c} else {return;};
+    if !cursor.is_finite() {return;}
+    if !transforms.is_empty() {
+        trail.push(time.seconds_since_startup(), cursor);
+    }
+    // held items stop at the edge of the window and the ui
+    let area = playable_area(&view, config.board_size);
+    for (mut transform, grabbed, size) in transforms.iter_mut() {
+        let at = clamp_to(cursor + grabbed.0, size.0, area);
+        transform.translation.x = at.x;
+        transform.translation.y = at.y;
     }
-    if !delta.is_finite() {return;}
-    transform.translation.x += delta.x;
-    transform.translation.y -= delta.y;
 }
 
 pub fn set_selected(
     mut commands: Commands,
-    mut set: ParamSet<(EventReader<ItemEvent>, EventWriter<ItemEvent>)>,
+    mut events: EventReader<ItemEvent>,
+    mut notices: EventWriter<ItemNotice>,
     res: Res<Seleced>,
+    selection: Res<Selection>,
+    cursor: Res<WorldCursor>,
+    mut transforms: Query<&mut Transform, With<ItemID>>,
+    grabbed: Query<Entity, With<Grabbed>>,
+    config: Res<ItemConfig>,
+    mut trail: ResMut<DragTrail>,
+    mut history: ResMut<History>,
+    mut lifted_from: Local<HashMap<Entity, Vec2>>,
+    locked: Query<(), With<Locked>>,
 ) {
-    let mut send = None;
-    for event in set.p0().iter() {
+    for event in events.iter() {
         match event {
-            ItemEvent::Pickup(e) => {commands.insert_resource(Seleced(Some(*e)))},
+            ItemEvent::Pickup(e) => {
+                if locked.contains(*e) {continue;}
+                history.begin();
+                lifted_from.clear();
+                // the rest of the selection comes along keeping its spacing
+                for item in selection.iter().chain(std::iter::once(e)) {
+                    if locked.contains(*item) {continue;}
+                    let at = match transforms.get(*item) {
+                        Ok(t) => t.translation.truncate(),
+                        _ => continue,
+                    };
+                    let offset = if let Some(c) = cursor.world {at - c} else {continue;};
+                    lifted_from.insert(*item, at);
+                    commands.entity(*item).insert(Grabbed(offset)).remove::<Velocity>();
+                }
+                commands.insert_resource(Seleced(Some(*e)))
+            },
             ItemEvent::Drop => {
                 if let Some(e) = res.0 {
-                    send = Some(ItemEvent::Droped(e));
+                    let velocity = trail.velocity();
+                    trail.clear();
+                    // a flung item is dropped when it lands instead
+                    let fling = velocity.is_finite() && velocity.length() > FLING_SPEED;
+                    if !fling {
+                        notices.send(ItemNotice::Droped(e));
+                    }
+                    for item in grabbed.iter() {
+                        commands.entity(item).remove::<Grabbed>();
+                        let moved = transforms.get(item).ok().zip(lifted_from.get(&item))
+                            .filter(|(t, from)| t.translation.truncate() != **from);
+                        if let Some((_, from)) = moved {
+                            history.record(Change::Moved(item, *from));
+                        }
+                        if fling {
+                            commands.entity(item).insert(Velocity(velocity));
+                            continue;
+                        }
+                        if !config.snap {continue;}
+                        if let Ok(mut t) = transforms.get_mut(item) {
+                            let at = snap(t.translation.truncate(), config.snap_size);
+                            t.translation.x = at.x;
+                            t.translation.y = at.y;
+                        }
+                    }
                     commands.insert_resource(Seleced(None));
                 } else {
                     warn!("Called drop when nothing was selected");
@@ -40,7 +129,4 @@ pub fn set_selected(
             _ => {},
         }
     }
-    if let Some(send) = send {
-        set.p1().send(send);
-    }
-}
\ No newline at end of file
+}
@@ -0,0 +1,85 @@
+use crate::{prelude::*, view::BoardView};
+
+use super::{spatial::SpatialIndex, physics::box_box_hit, layout::{playable_area, clamp_to}};
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use crate::item::spatial::SpatialIndex;
+    use super::{find_spot, top_left, GAP};
+
+    #[test]
+    fn free_spots() {
+        let area = (Vec2::new(-500., -500.), Vec2::new(500., 500.));
+        let size = Vec2::splat(100.);
+        let mut index = SpatialIndex::new(100.);
+        assert_eq!(find_spot(Vec2::ZERO, size, area, &index, &[], &[]), Vec2::ZERO);
+        index.insert(Entity::from_raw(0), Vec3::ZERO, size);
+        let next = find_spot(Vec2::ZERO, size, area, &index, &[], &[]);
+        assert_eq!(next.length(), 100. + GAP, "the closest free spot is right next to it");
+        let after = find_spot(Vec2::ZERO, size, area, &index, &[next], &[]);
+        assert!(after != next && after.length() == 100. + GAP, "spots taken this frame are skipped");
+        // what was used up to make it is not in the way
+        assert_eq!(find_spot(Vec2::ZERO, size, area, &index, &[], &[Entity::from_raw(0)]), Vec2::ZERO);
+        // against the edge the search stays inside
+        let corner = find_spot(Vec2::new(1000., 1000.), size, area, &index, &[], &[]);
+        assert_eq!(corner, Vec2::new(450., 450.));
+        assert_eq!(top_left(area, size), Vec2::new(-450., 450.));
+    }
+
+    #[test]
+    fn full_area() {
+        let area = (Vec2::ZERO, Vec2::splat(100.));
+        let size = Vec2::splat(100.);
+        let mut index = SpatialIndex::new(100.);
+        index.insert(Entity::from_raw(0), Vec3::new(50., 50., 0.), size);
+        // nowhere free so it goes where it was asked
+        assert_eq!(find_spot(Vec2::new(50., 50.), size, area, &index, &[], &[]), Vec2::new(50., 50.));
+    }
+}
+
+/// space left between items placed next to each other
+const GAP: f32 = 10.;
+/// how many rings of item sized steps are tried around the preferred spot
+const MAX_RINGS: i32 = 10;
+
+fn is_free(at: Vec2, size: Vec2, index: &SpatialIndex, claimed: &[Vec2], leaving: &[Entity]) -> bool {
+    index.overlapping(at, size, None).iter().all(|(e, _)| leaving.contains(e))
+        && !claimed.iter().any(|other| box_box_hit(size, at, size, *other))
+}
+
+/// the free spot closest to `preferred` inside `area`, rings of item sized steps are tried
+/// from the inside out. `claimed` are spots given out this frame that the index doesn't have yet,
+/// `leaving` are items still in the index that are going this frame so their spots are free
+pub fn find_spot(preferred: Vec2, size: Vec2, area: (Vec2, Vec2), index: &SpatialIndex, claimed: &[Vec2], leaving: &[Entity]) -> Vec2 {
+    let step = size + GAP;
+    for ring in 0..=MAX_RINGS {
+        let mut offsets: Vec<Vec2> = (-ring..=ring)
+            .flat_map(|x| (-ring..=ring).map(move |y| IVec2::new(x, y)))
+            .filter(|o| o.x.abs().max(o.y.abs()) == ring)
+            .map(|o| o.as_vec2() * step)
+            .collect();
+        offsets.sort_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+        for offset in offsets {
+            let at = clamp_to(preferred + offset, size, area);
+            if is_free(at, size, index, claimed, leaving) {return at;}
+        }
+    }
+    clamp_to(preferred, size, area)
+}
+
+/// where a `size` item sits in the top left corner of `area`
+pub fn top_left((min, max): (Vec2, Vec2), size: Vec2) -> Vec2 {
+    Vec2::new(min.x + size.x / 2., max.y - size.y / 2.)
+}
+
+/// somewhere random on screen that is not under the ui
+pub fn random_spot(view: &BoardView, board_size: Vec2, size: Vec2) -> Vec2 {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+    let (min, max) = playable_area(view, board_size);
+    let half = size / 2.;
+    let x = if min.x + half.x < max.x - half.x {rng.gen_range((min.x + half.x)..(max.x - half.x))} else {min.x};
+    let y = if min.y + half.y < max.y - half.y {rng.gen_range((min.y + half.y)..(max.y - half.y))} else {min.y};
+    Vec2::new(x, y)
+}
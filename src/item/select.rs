@@ -0,0 +1,125 @@
+use std::collections::HashSet;
+
+use crate::{prelude::*, view::BoardView};
+
+use super::{spatial::SpatialIndex, history::History, layers::part};
+
+/// every item the player has selected, dragging one of them drags them all
+#[derive(Debug, Default, Deref, DerefMut)]
+pub struct Selection(pub HashSet<Entity>);
+
+/// on a selected item, points at its highlight
+#[derive(Debug, Component)]
+pub struct Selected(Entity);
+
+/// the box being dragged out from `0` to select everything it touches
+#[derive(Debug, Component)]
+pub struct RubberBand(Vec2);
+
+pub fn shift_held(keys: &Input<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::LShift, KeyCode::RShift])
+}
+
+pub fn ctrl_held(keys: &Input<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::LControl, KeyCode::RControl])
+}
+
+/// `screen` is on top of the tool tip bar or item space
+pub fn over_ui(screen: Vec2, view: &BoardView) -> bool {
+    screen.y < crate::ui::TOOLTIP_HEIGHT || crate::ui::over_item_space(screen, view.size)
+}
+
+pub fn rubber_band(
+    mut commands: Commands,
+    cursor: Res<WorldCursor>,
+    view: Res<BoardView>,
+    keys: Res<Input<KeyCode>>,
+    index: Res<SpatialIndex>,
+    mut selection: ResMut<Selection>,
+    mut band: Query<(Entity, &RubberBand, &mut Transform)>,
+    ui: Query<&Interaction>,
+) {
+    let (world, screen) = if let (Some(w), Some(s)) = (cursor.world, cursor.screen) {(w, s)} else {return;};
+    if cursor.just_pressed && index.top_at_point(world).is_none() && !over_ui(screen, &view) && !crate::ui::pointer_on_ui(&ui) {
+        if !shift_held(&keys) {
+            selection.clear();
+        }
+        commands.spawn_bundle(SpriteBundle {
+            sprite: Sprite { color: Color::rgba(0.3, 0.6, 1.0, 0.25), custom_size: Some(Vec2::ONE), ..Default::default() },
+            transform: Transform::from_translation(world.extend(950.)).with_scale(Vec3::ZERO),
+            ..Default::default()
+        })
+        .insert(RubberBand(world));
+        return;
+    }
+    for (e, band, mut transform) in band.iter_mut() {
+        let center = (band.0 + world) / 2.;
+        let size = (band.0 - world).abs();
+        transform.translation = center.extend(950.);
+        transform.scale = size.extend(1.);
+        if !cursor.pressed {
+            for (hit, _) in index.overlapping(center, size, None) {
+                selection.insert(hit);
+            }
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+pub fn group_actions(
+    keys: Res<Input<KeyCode>>,
+    mut selection: ResMut<Selection>,
+    items: Query<(Entity, &ItemID, &Transform), With<Size>>,
+    mut events: EventWriter<ItemEvent>,
+    mut history: ResMut<History>,
+) {
+    if selection.is_empty() {return;}
+    let trash = ItemID::new("Trash");
+    if keys.any_just_pressed([KeyCode::Delete, KeyCode::Back]) {
+        // nothing can be thrown away until the trash is found
+        let bin = if let Some((bin, _, _)) = items.iter().find(|(_, id, _)| **id == trash) {bin} else {return;};
+        // the same as dropping each in so it can be undone
+        history.begin();
+        for e in selection.drain() {
+            let (_, id, _) = if let Ok(i) = items.get(e) {i} else {continue;};
+            if id == &trash {continue;}
+            events.send(ItemEvent::CheckCombine(e, bin));
+        }
+    } else if ctrl_held(&keys) && keys.just_pressed(KeyCode::D) {
+        history.begin();
+        for e in selection.iter() {
+            let (_, id, t) = if let Ok(i) = items.get(*e) {i} else {continue;};
+            if id == &trash {continue;}
+            events.send(ItemEvent::PlayerSpawnAt(*id, t.translation.truncate().extend(0.0)));
+        }
+    }
+}
+
+pub fn highlight_selection(
+    mut commands: Commands,
+    mut selection: ResMut<Selection>,
+    items: Query<(Option<&Selected>, &super::physics::Size), With<ItemID>>,
+    marked: Query<(Entity, &Selected)>,
+    icons: Res<crate::ui::UiIcons>,
+) {
+    // combined or deleted items drop out of the selection
+    if selection.iter().any(|e| !items.contains(*e)) {
+        selection.retain(|e| items.contains(*e));
+    }
+    for (e, selected) in marked.iter() {
+        if !selection.contains(&e) {
+            commands.entity(selected.0).despawn_recursive();
+            commands.entity(e).remove::<Selected>();
+        }
+    }
+    for e in selection.iter() {
+        let size = if let Ok((None, size)) = items.get(*e) {size} else {continue;};
+        let highlight = commands.spawn_bundle(SpriteBundle {
+            sprite: Sprite { color: Color::rgba(1.0, 0.9, 0.2, 0.8), custom_size: Some(size.0 * 1.15), ..Default::default() },
+            texture: icons.get("item_frame"),
+            transform: Transform::from_translation(Vec3::Z * part::HIGHLIGHT),
+            ..Default::default()
+        }).id();
+        commands.entity(*e).add_child(highlight).insert(Selected(highlight));
+    }
+}
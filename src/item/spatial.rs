@@ -0,0 +1,153 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::prelude::*;
+
+use super::physics::{box_box_hit, box_box_overlap, box_point_hit};
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use super::SpatialIndex;
+
+    #[test]
+    fn point_and_box() {
+        let mut index = SpatialIndex::new(100.);
+        let a = Entity::from_raw(0);
+        let b = Entity::from_raw(1);
+        let c = Entity::from_raw(2);
+        index.insert(a, Vec3::new(0., 0., 0.1), Vec2::splat(100.));
+        index.insert(b, Vec3::new(30., 30., 0.2), Vec2::splat(100.));
+        index.insert(c, Vec3::new(1000., -1000., 0.3), Vec2::splat(100.));
+        assert_eq!(index.top_at_point(Vec2::new(10., 10.)), Some(b));
+        assert_eq!(index.at_point(Vec2::new(-40., -40.)), vec![a]);
+        assert_eq!(index.top_at_point(Vec2::new(500., 500.)), None);
+        let hits = index.overlapping(Vec2::new(20., 20.), Vec2::splat(100.), Some(a));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, b);
+        index.insert(b, Vec3::new(1000., -1000., 0.2), Vec2::splat(100.));
+        assert_eq!(index.top_at_point(Vec2::new(10., 10.)), Some(a));
+        assert_eq!(index.top_at_point(Vec2::new(1000., -1000.)), Some(c));
+        index.remove(c);
+        assert_eq!(index.top_at_point(Vec2::new(1000., -1000.)), Some(b));
+        assert_eq!(index.len(), 2);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    center: Vec2,
+    size: Vec2,
+    z: f32,
+}
+
+/// uniform grid over every board item so picking and overlap checks
+/// only look at the items near where they are asked about
+#[derive(Debug)]
+pub struct SpatialIndex {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<Entity>>,
+    entries: HashMap<Entity, Entry>,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        SpatialIndex::new(100.)
+    }
+}
+
+impl SpatialIndex {
+    pub fn new(cell_size: f32) -> SpatialIndex {
+        SpatialIndex { cell_size, cells: HashMap::default(), entries: HashMap::default() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn cell(&self, point: Vec2) -> IVec2 {
+        (point / self.cell_size).floor().as_ivec2()
+    }
+
+    fn cells_for(&self, center: Vec2, size: Vec2) -> impl Iterator<Item = IVec2> {
+        let min = self.cell(center - size / 2.);
+        let max = self.cell(center + size / 2.);
+        (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+    }
+
+    pub fn insert(&mut self, entity: Entity, at: Vec3, size: Vec2) {
+        self.remove(entity);
+        let entry = Entry { center: at.truncate(), size, z: at.z };
+        let cells: Vec<IVec2> = self.cells_for(entry.center, size).collect();
+        for cell in cells {
+            self.cells.entry(cell).or_default().push(entity);
+        }
+        self.entries.insert(entity, entry);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        let entry = if let Some(e) = self.entries.remove(&entity) {e} else {return;};
+        let cells: Vec<IVec2> = self.cells_for(entry.center, entry.size).collect();
+        for cell in cells {
+            if let Some(list) = self.cells.get_mut(&cell) {
+                list.retain(|e| *e != entity);
+                if list.is_empty() {self.cells.remove(&cell);}
+            }
+        }
+    }
+
+    /// everything under `point`, top most first
+    pub fn at_point(&self, point: Vec2) -> Vec<Entity> {
+        let mut hits: Vec<(Entity, f32)> = Vec::new();
+        if let Some(list) = self.cells.get(&self.cell(point)) {
+            for e in list {
+                let entry = &self.entries[e];
+                if box_point_hit(entry.size, entry.center, point) {
+                    hits.push((*e, entry.z));
+                }
+            }
+        }
+        hits.sort_by(|(_, a),(_, b)| b.partial_cmp(a).unwrap_or(Ordering::Greater));
+        hits.into_iter().map(|(e, _)| e).collect()
+    }
+
+    pub fn top_at_point(&self, point: Vec2) -> Option<Entity> {
+        self.at_point(point).first().copied()
+    }
+
+    /// everything touching the box, biggest overlap first
+    pub fn overlapping(&self, center: Vec2, size: Vec2, ignore: Option<Entity>) -> Vec<(Entity, f32)> {
+        let mut seen = Vec::new();
+        let mut hits = Vec::new();
+        for cell in self.cells_for(center, size) {
+            let list = if let Some(l) = self.cells.get(&cell) {l} else {continue;};
+            for e in list {
+                if Some(*e) == ignore || seen.contains(e) {continue;}
+                seen.push(*e);
+                let entry = &self.entries[e];
+                if box_box_hit(size, center, entry.size, entry.center) {
+                    hits.push((*e, box_box_overlap(size, center, entry.size, entry.center)));
+                }
+            }
+        }
+        hits.sort_by(|a,b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Greater));
+        hits
+    }
+}
+
+pub fn update_index(
+    mut index: ResMut<SpatialIndex>,
+    moved: Query<(Entity, &Transform, &super::physics::Size), (With<ItemID>, Or<(Changed<Transform>, Changed<super::physics::Size>)>)>,
+    removed: RemovedComponents<ItemID>,
+) {
+    for e in removed.iter() {
+        index.remove(e);
+    }
+    for (e, t, s) in moved.iter() {
+        index.insert(e, t.translation, s.0);
+    }
+}
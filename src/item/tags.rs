@@ -1,37 +1,96 @@
 use std::collections::{HashSet, HashMap, hash_map::DefaultHasher};
 use std::num::ParseIntError;
-use std::sync::Mutex;
-use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 use std::hash::{Hash, Hasher};
 use bevy::prelude::*;
 use serde::{Serialize, Deserialize};
 
+use crate::error::GameError;
+
 #[cfg(test)]
 mod test {
     use super::Tag;
     use super::Tags;
+    use super::TagRegistry;
     #[test]
     fn to_string() {
-        let tag = Tag::new("Hi").unwrap();
-        let name = tag.name();
-        assert_eq!("Hi", name);
+        let mut registry = TagRegistry::default();
+        let tag = registry.tag("Hi").unwrap();
+        assert_eq!("Hi", registry.name(&tag));
     }
     #[test]
     fn no_name() {
+        let registry = TagRegistry::default();
         let tag = Tag(0x64);
-        let name = tag.name();
-        assert_eq!(name, "0x64");
+        assert_eq!(registry.name(&tag), "0x64");
     }
     #[test]
     fn from_hex() {
-        let tag = Tag::new("0x64").unwrap();
-        assert_eq!(tag.name(), "0x64");
+        let mut registry = TagRegistry::default();
+        let tag = registry.tag("0x64").unwrap();
+        assert_eq!(tag, Tag(0x64));
+        assert_eq!(registry.name(&tag), "0x64");
     }
     #[test]
     fn from_int() {
-        let tag = Tag::new("69").unwrap();
-        assert_eq!(tag.name(), "0x45");
+        let mut registry = TagRegistry::default();
+        let tag = registry.tag("69").unwrap();
+        assert_eq!(tag, Tag(0x45));
+        assert_eq!(registry.name(&tag), "69");
+    }
+    #[test]
+    fn short_and_bad_numbers() {
+        assert_eq!(Tag::new("5").unwrap(), Tag(5));
+        assert!(Tag::new("0x").is_ok());
+        assert!(Tag::new("3d").is_ok());
+        assert!(Tag::new("  ").is_err());
+    }
+    #[test]
+    fn display_name() {
+        let mut registry = TagRegistry::default();
+        let tag = registry.tag("make_jam").unwrap();
+        assert_eq!(tag, Tag::new("make jam").unwrap());
+        assert_eq!(registry.display_name(&tag), "make_jam");
+        registry.info_mut(tag).display_name = Some("Jam Fruit".to_string());
+        assert_eq!(registry.display_name(&tag), "Jam Fruit");
+    }
+    #[test]
+    fn hierarchy() {
+        let mut registry = TagRegistry::default();
+        let citrus = registry.tag("food/fruit/citrus").unwrap();
+        let fruit = registry.tag("food/fruit").unwrap();
+        let food = registry.tag("food").unwrap();
+        let perishable = registry.tag("perishable").unwrap();
+        registry.set_parent(food, perishable);
+        assert!(registry.is_a(&citrus, &food));
+        assert!(registry.is_a(&citrus, &perishable));
+        assert!(!registry.is_a(&fruit, &citrus));
+        registry.set_parent(perishable, citrus);
+        assert_eq!(registry.parent(&perishable), None);
+        let below = registry.descendants(&food);
+        assert_eq!(below.len(), 3);
+        let mut tags = Tags::default();
+        tags.insert(citrus);
+        assert!(tags.has(&perishable, &registry));
+        assert!(!tags.contains(&perishable));
+    }
+    #[test]
+    fn inherited_style() {
+        use bevy::prelude::Color;
+        let mut registry = TagRegistry::default();
+        let fruit = registry.tag("food/fruit").unwrap();
+        let food = registry.tag("food").unwrap();
+        registry.info_mut(food).style.tint = Some(Color::GREEN);
+        registry.info_mut(food).style.glow = Some(Color::RED);
+        registry.info_mut(fruit).style.tint = Some(Color::BLUE);
+        let mut tags = Tags::default();
+        tags.insert(fruit);
+        let style = registry.style(&tags);
+        assert_eq!(style.tint, Some(Color::BLUE));
+        assert_eq!(style.glow, Some(Color::RED));
+        assert!(style.badge.is_none());
+        assert!(registry.style(&Tags::default()).is_empty());
     }
     #[test]
     fn serialize() {
@@ -49,7 +108,7 @@ mod test {
     }
     #[test]
     fn deserialize() {
-        let tags: Tags = ron::from_str(r#"["Meta","Hi","0x45","0x64"]"#).unwrap();
+        let tags: Tags = ron::from_str(r#"["Meta","Hi","0x45","0x64",""]"#).unwrap();
         assert!(tags.contains(&Tag::new("Hi").unwrap()));
         assert!(tags.contains(&Tag::new("Meta").unwrap()));
         assert!(tags.contains(&Tag::new("0x64").unwrap()));
@@ -57,10 +116,6 @@ mod test {
     }
 }
 
-lazy_static! {
-    static ref TAG_TO_STRING: Mutex<HashMap<Tag, String>> = Mutex::new(HashMap::default());
-}
-
 #[derive(Debug, Default)]
 pub struct Tags(HashSet<Tag>);
 
@@ -79,6 +134,10 @@ impl Tags {
             self.0.insert(*tag);
         }
     }
+    /// like `contains` but a child of `tag` also counts
+    pub fn has(&self, tag: &Tag, registry: &TagRegistry) -> bool {
+        self.0.iter().any(|t| registry.is_a(t, tag))
+    }
 }
 
 impl Serialize for Tags {
@@ -101,7 +160,10 @@ impl<'de> Deserialize<'de> for Tags {
         let data: Vec<String> = Vec::deserialize(deserializer)?;
         let mut set = HashSet::default();
         for tag in data {
-            set.insert(Tag::from_str(&tag).unwrap());
+            match Tag::from_str(&tag) {
+                Ok(tag) => {set.insert(tag);},
+                Err(e) => warn!("skipping tag {:?}: {}", tag, e),
+            }
         }
         Ok(Tags(set))
     }
@@ -114,58 +176,251 @@ impl Tag {
     pub fn new(name: &str) -> Result<Tag, TagError> {
         Tag::from_str(name)
     }
+    /// `0x..` is read as hex, all digits as a number, anything else is hashed as a name
     pub fn from_str(str: &str) -> Result<Tag, TagError> {
         let str = str.trim();
-        if str.starts_with(|c: char| c.is_numeric()) {
-            let id = match &str[..2] {
-                "0x" | "0X" => {u64::from_str_radix(&str[2..], 16)?},
-                _ => {str.parse()?},
-            };
-            Ok(Tag(id))
-        } else {
-            let mut hasher = DefaultHasher::default();
-            str.hash(&mut hasher);
-            let tag = Tag(hasher.finish());
-            if let Ok(mut lock) = TAG_TO_STRING.lock() {
-                lock.insert(tag, str.to_string());
-                Ok(tag)
-            } else {
-                Err(TagError::LockFailed)
+        if str.is_empty() {return Err(TagError::Empty);}
+        if let Some(hex) = str.strip_prefix("0x").or_else(|| str.strip_prefix("0X")) {
+            if !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Ok(Tag(u64::from_str_radix(hex, 16)?));
             }
+        } else if str.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(Tag(str.parse()?));
         }
+        let mut hasher = DefaultHasher::default();
+        str.replace(char::is_whitespace, "_").hash(&mut hasher);
+        Ok(Tag(hasher.finish()))
     }
-    pub fn name(&self) -> String {
-        match self.get_name() {
-            Ok(name) => {name},
-            Err(e) => {match e {
-                TagError::LockFailed => {error!("Name Lookup is poisoned")},
-                TagError::NoName => {warn!("no name registred for Tag({:#X})", self.0)},
-                _ => unreachable!(),
-            }; format!("{:#X}", self.0)}
-        }
-    }
-    pub fn get_name(&self) -> Result<String, TagError> {
-        let lock = if let Ok(lock) = TAG_TO_STRING.lock() {lock} else {return Err(TagError::LockFailed);};
-        if let Some(name) = lock.get(self) {
-            Ok(name.clone())
-        } else {
-            Err(TagError::NoName)
-        }
+}
+
+impl std::fmt::Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#X}", self.0)
     }
 }
 
 #[derive(Debug, Error)]
 pub enum TagError {
-    #[error("LockFailed")]
-    LockFailed,
-    #[error("No Regestered Name")]
-    NoName,
+    #[error("Tag is empty")]
+    Empty,
     #[error("Parse Error")]
     ParseError(#[from] ParseIntError)
 }
 
-impl ToString for Tag {
-    fn to_string(&self) -> String {
-        self.name()
+#[derive(Debug, Default, Clone)]
+pub struct TagInfo {
+    pub name: Option<String>,
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub icon: Option<Handle<Image>>,
+    pub parent: Option<Tag>,
+    pub style: TagStyle,
+}
+
+/// how items with a tag are drawn, children inherit from their parent tag
+#[derive(Debug, Default, Clone)]
+pub struct TagStyle {
+    pub tint: Option<Color>,
+    pub badge: Option<Handle<Image>>,
+    pub glow: Option<Color>,
+}
+
+impl TagStyle {
+    pub fn is_empty(&self) -> bool {
+        self.tint.is_none() && self.badge.is_none() && self.glow.is_none()
+    }
+
+    /// fills anything not already set from `other`
+    fn fill(&mut self, other: &TagStyle) {
+        if self.tint.is_none() {self.tint = other.tint;}
+        if self.badge.is_none() {self.badge = other.badge.clone();}
+        if self.glow.is_none() {self.glow = other.glow;}
+    }
+}
+
+/// names, descriptions and icons for tags, loaded from `.vt` files
+#[derive(Debug, Default)]
+pub struct TagRegistry {
+    tags: HashMap<Tag, TagInfo>,
+}
+
+impl FromWorld for TagRegistry {
+    fn from_world(world: &mut World) -> Self {
+        let dir = crate::AssetDir::of(world);
+        let asset_server = world.resource::<AssetServer>();
+        let mut registry = TagRegistry::default();
+        if let Err(e) = registry.load_folder(&dir.0, asset_server) {
+            error!("{}", e);
+        }
+        registry
     }
-}
\ No newline at end of file
+}
+
+impl TagRegistry {
+    /// parses a tag and remembers the name it was written as;
+    /// a path like `food/fruit` also registers `food` as its parent
+    pub fn tag(&mut self, name: &str) -> Result<Tag, TagError> {
+        let name = name.trim();
+        let tag = Tag::from_str(name)?;
+        let parent = match name.rsplit_once('/') {
+            Some((parent, _)) if !parent.trim().is_empty() => Some(self.tag(parent)?),
+            _ => None,
+        };
+        let info = self.info_mut(tag);
+        if info.name.is_none() {
+            info.name = Some(name.replace(char::is_whitespace, "_"));
+        }
+        if info.parent.is_none() {
+            info.parent = parent;
+        }
+        Ok(tag)
+    }
+
+    pub fn tags(&mut self, names: &[String]) -> Tags {
+        let mut tags = Tags::default();
+        for name in names {
+            match self.tag(name) {
+                Ok(tag) => tags.insert(tag),
+                Err(e) => warn!("skipping tag {:?}: {}", name, e),
+            }
+        }
+        tags
+    }
+
+    pub fn info(&self, tag: &Tag) -> Option<&TagInfo> {
+        self.tags.get(tag)
+    }
+
+    pub fn info_mut(&mut self, tag: Tag) -> &mut TagInfo {
+        self.tags.entry(tag).or_default()
+    }
+
+    pub fn name(&self, tag: &Tag) -> String {
+        if let Some(name) = self.info(tag).and_then(|i| i.name.as_ref()) {
+            name.clone()
+        } else {
+            tag.to_string()
+        }
+    }
+
+    pub fn display_name(&self, tag: &Tag) -> String {
+        if let Some(name) = self.info(tag).and_then(|i| i.display_name.as_ref()) {
+            name.clone()
+        } else {
+            self.name(tag)
+        }
+    }
+
+    pub fn description(&self, tag: &Tag) -> Option<&str> {
+        self.info(tag).and_then(|i| i.description.as_deref())
+    }
+
+    pub fn icon(&self, tag: &Tag) -> Option<Handle<Image>> {
+        self.info(tag).and_then(|i| i.icon.clone())
+    }
+
+    pub fn parent(&self, tag: &Tag) -> Option<Tag> {
+        self.info(tag).and_then(|i| i.parent)
+    }
+
+    pub fn set_parent(&mut self, tag: Tag, parent: Tag) {
+        if self.is_a(&parent, &tag) {
+            warn!("{} can not be a parent of {}, it would make a loop", self.name(&parent), self.name(&tag));
+            return;
+        }
+        self.info_mut(tag).parent = Some(parent);
+    }
+
+    /// the style for an item with `tags`, closer tags win over their parents
+    pub fn style(&self, tags: &Tags) -> TagStyle {
+        let mut sorted: Vec<&Tag> = tags.iter().collect();
+        sorted.sort_by_key(|t| self.name(t));
+        let mut style = TagStyle::default();
+        for tag in sorted {
+            let mut current = Some(*tag);
+            for _ in 0..=self.tags.len() {
+                let tag = if let Some(t) = current {t} else {break;};
+                if let Some(info) = self.info(&tag) {
+                    style.fill(&info.style);
+                }
+                current = self.parent(&tag);
+            }
+        }
+        style
+    }
+
+    /// true if `tag` is `ancestor` or sits anywhere below it
+    pub fn is_a(&self, tag: &Tag, ancestor: &Tag) -> bool {
+        let mut current = Some(*tag);
+        // a bad parent chain can not loop forever
+        for _ in 0..=self.tags.len() {
+            match current {
+                Some(t) if &t == ancestor => return true,
+                Some(t) => current = self.parent(&t),
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// `tag` and every tag that inherits from it
+    pub fn descendants(&self, tag: &Tag) -> Vec<Tag> {
+        let mut found = vec![*tag];
+        for other in self.tags.keys() {
+            if other != tag && self.is_a(other, tag) {
+                found.push(*other);
+            }
+        }
+        found
+    }
+
+    pub fn load_folder<P>(&mut self, path: P, asset_server: &AssetServer) -> Result<(), GameError> where P: AsRef<Path> {
+        use std::fs;
+        for file in fs::read_dir(path)? {
+            let file = match file {Ok(f) => {f}, Err(e) => {error!("file: {}", e); continue;},};
+            if file.metadata()?.is_dir() {
+                if let Err(e) = self.load_folder(file.path(), asset_server) {
+                    error!("Rec: {}", e);
+                };
+                continue;
+            }
+            if let Some(ext) = file.path().extension() {if ext != "vt" {continue;}}
+            if let Err(e) = self.load(file.path(), asset_server) {error!("load: {}", e)};
+        }
+        Ok(())
+    }
+
+    pub fn load<P>(&mut self, path: P, asset_server: &AssetServer) -> Result<(), GameError>
+    where P: Into<PathBuf>
+    {
+        use super::items::{read_fields, unwrap_or_t};
+        let mut path: PathBuf = path.into();
+        let data = std::fs::read_to_string(&path)?;
+        path.pop();
+        let path = if let Ok(new_path) = path.strip_prefix("./assets") {new_path} else {&path};
+        for entry in data.split("{next}") {
+            if entry.trim().is_empty() {continue;}
+            let map = read_fields(entry);
+            let name: String = unwrap_or_t(&map, "tag")?;
+            let tag = self.tag(&name)?;
+            let display_name = unwrap_or_t::<String>(&map, "display_name").ok();
+            let description = unwrap_or_t::<String>(&map, "description").ok();
+            let icon = unwrap_or_t::<String>(&map, "icon").ok().map(|icon| asset_server.load(path.join(icon)));
+            if let Ok(parent) = unwrap_or_t::<String>(&map, "parent") {
+                let parent = self.tag(&parent)?;
+                self.set_parent(tag, parent);
+            }
+            let tint = unwrap_or_t::<(f32, f32, f32, f32)>(&map, "tint").ok().map(|(r, g, b, a)| Color::rgba(r, g, b, a));
+            let glow = unwrap_or_t::<(f32, f32, f32, f32)>(&map, "glow").ok().map(|(r, g, b, a)| Color::rgba(r, g, b, a));
+            let badge = unwrap_or_t::<String>(&map, "badge").ok().map(|badge| asset_server.load(path.join(badge)));
+            let info = self.info_mut(tag);
+            if tint.is_some() {info.style.tint = tint;}
+            if glow.is_some() {info.style.glow = glow;}
+            if badge.is_some() {info.style.badge = badge;}
+            if display_name.is_some() {info.display_name = display_name;}
+            if description.is_some() {info.description = description;}
+            if icon.is_some() {info.icon = icon;}
+        }
+        Ok(())
+    }
+}
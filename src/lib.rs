@@ -3,21 +3,48 @@ pub mod prelude {
     pub use crate::ui::ui_config::UiConfig;
     pub use bevy::prelude::*;
     pub use crate::item::ItemID;
-    pub use crate::item::ItemEvent;
-    pub use crate::item::tags::{Tags, Tag};
+    pub use crate::item::{ItemEvent, ItemNotice};
+    pub use crate::item::tags::{Tags, Tag, TagRegistry};
     pub use crate::item::physics::Size;
     pub use crate::item::Items;
     pub use crate::MainCam;
+    pub use crate::view::WorldCursor;
 }
 
+pub mod camera;
+pub mod controls;
 pub mod error;
 pub mod item;
 pub mod one_offs;
 pub mod ui;
 pub mod recipies;
 pub mod serde;
+pub mod script;
 pub mod story;
 pub mod sound;
+pub mod view;
+pub mod testing;
 
 #[derive(bevy::prelude::Component)]
-pub struct MainCam;
\ No newline at end of file
+pub struct MainCam;
+
+/// the folder items, tags, recipies, scripts and the item config are read from, tests point it at their own mods
+#[derive(Debug, Clone)]
+pub struct AssetDir(pub std::path::PathBuf);
+
+impl Default for AssetDir {
+    fn default() -> Self {
+        AssetDir(std::path::PathBuf::from("./assets"))
+    }
+}
+
+impl AssetDir {
+    /// the one `world` was given, `./assets` if none was
+    pub fn of(world: &bevy::prelude::World) -> AssetDir {
+        world.get_resource::<AssetDir>().cloned().unwrap_or_default()
+    }
+
+    pub fn file(&self, name: &str) -> std::path::PathBuf {
+        self.0.join(name)
+    }
+}
\ No newline at end of file
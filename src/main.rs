@@ -9,12 +9,17 @@ fn main() {
         .add_plugin(bevy::diagnostic::DiagnosticsPlugin)
         .add_plugin(bevy::diagnostic::FrameTimeDiagnosticsPlugin)
         .add_plugin(bevy::diagnostic::EntityCountDiagnosticsPlugin)
+        .add_plugin(void_a_nomicon::view::ViewPlugin)
+        .add_plugin(void_a_nomicon::camera::CameraPlugin)
+        .add_plugin(void_a_nomicon::controls::ControlsPlugin)
         .add_plugin(void_a_nomicon::one_offs::OneOffPlugin)
         .add_plugin(void_a_nomicon::ui::UiPlugin)
         .add_plugin(void_a_nomicon::item::ItemPlugin)
+        .add_plugin(void_a_nomicon::item::ItemInputPlugin)
         .add_plugin(void_a_nomicon::recipies::RecipiePlugin)
         .add_plugin(void_a_nomicon::serde::SaveLoadPlugin)
         .add_plugin(void_a_nomicon::story::StoryPlugin)
+        .add_plugin(void_a_nomicon::script::ScriptPlugin)
         .add_plugin(void_a_nomicon::sound::SoundPlugin)
         .insert_resource(WindowDescriptor{
             width: 1280.,
@@ -1,13 +1,24 @@
 use std::{collections::{HashMap, HashSet}, path::Path};
 
-use crate::prelude::*;
+use crate::{prelude::*, item::{ItemStage, ItemSystem, history::{History, Change}}};
+
+mod expr;
+mod preview;
+mod notice;
+
+pub use expr::Matcher;
 
 pub struct RecipiePlugin;
 
 impl Plugin for RecipiePlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Recipies::default());
-        app.add_system(combine);
+        app.init_resource::<crate::AssetDir>();
+        app.add_system_to_stage(ItemStage, combine.label(ItemSystem::Combine).after(ItemSystem::Drop));
+        app.init_resource::<preview::Preview>();
+        app.add_system(preview::combine_preview);
+        app.init_resource::<notice::NoticeStyle>();
+        app.add_system(notice::fade_notices);
         app.add_startup_system(load_recipies);
         app.add_startup_system(set_trash);
         app.init_resource::<MadeSound>();
@@ -17,40 +28,43 @@ impl Plugin for RecipiePlugin {
 fn load_recipies(
     mut recipies: ResMut<Recipies>,
     items: Res<Items>,
+    registry: Res<TagRegistry>,
+    dir: Res<crate::AssetDir>,
 ) {
-    use std::fs;
-    if let Ok(data) = fs::read_to_string("./assets/made.sav") {
-        let mut made = HashSet::new();
-        for line in data.split('\n') {
-            let id:(ItemID, ItemID) = if let Ok(i) = ron::from_str(line) {i} else {continue;};
-            made.insert(id);
-        }
-        recipies.made = made;
-    }
-    for recipie in load_recipies_from_folder("./assets")
+    for recipie in load_recipies_from_folder(&dir.0)
     {
         match recipie {
-            RecipieType::AddRecipie { item1, item2, makes, rank } => recipies.insert(item1, item2, makes, rank),
-            RecipieType::AddTagRecipie { item, has, makes, rank } => {
-                if let Some(items) = items.with_tag(&has) {
-                    for item2 in items {
-                        recipies.insert(item, *item2, makes, rank);
+            // already given to the recipies in its own file
+            RecipieType::DefaultRepeat(_) => {},
+            RecipieType::AddRecipie { item1, item2, makes, rank, repeat } => recipies.insert(items.resolve(item1), items.resolve(item2), items.resolve(makes), rank, repeat),
+            RecipieType::MatchRecipie { mut first, mut second, makes, rank, repeat } => {
+                first.resolve(&items);
+                second.resolve(&items);
+                let items2 = matching(&second, &items, &registry);
+                let mut pairs = HashSet::new();
+                for item1 in matching(&first, &items, &registry) {
+                    for item2 in items2.iter() {
+                        pairs.insert(item1.first(*item2));
                     }
                 }
-            },
-            RecipieType::MakeTagRecipie { item1_has, item2_has, makes, rank } => {
-                if let (Some(items1), Some(items2)) = (items.with_tag(&item1_has), items.with_tag(&item2_has)) {
-                    for item1 in items1 {
-                        for item2 in items2.clone() {
-                            recipies.insert(*item1, *item2, makes, rank);
-                        }
-                    }
+                for (item1, item2) in pairs {
+                    recipies.insert(item1, item2, items.resolve(makes), rank, repeat);
                 }
             },
         }
     }
 }
 
+fn matching(matcher: &Matcher, items: &Items, registry: &TagRegistry) -> Vec<ItemID> {
+    if let Matcher::Item(item) = matcher {
+        return vec![*item];
+    }
+    let none = Tags::default();
+    items.all().into_iter()
+        .filter(|id| matcher.matches(id, items.tags(id).unwrap_or(&none), registry))
+        .collect()
+}
+
 fn load_recipies_from_folder<P>(path: P) -> Vec<RecipieType> where P: AsRef<std::path::Path> {
     use std::fs;
     let mut found = Vec::new();
@@ -79,11 +93,13 @@ fn load_recipies_from_file<P>(path: P) -> Vec<RecipieType> where P:AsRef<std::pa
         Err(e) => {error!("{}", e); return res;}
     };
     for line in data.lines() {
+        if line.trim().is_empty() {continue;}
         match RecipieType::from_str(line) {
             Ok(r) => res.push(r),
             Err(e) => error!("{}",e),
         }
     }
+    res = with_file_repeat(res);
     res
 }
 
@@ -97,7 +113,97 @@ fn set_trash(
 #[cfg(test)]
 mod test {
     use crate::prelude::*;
-    use super::{Recipie,Recipies};
+    use super::{Recipie,Recipies,Outcome,Repeat,RecipieType,with_file_repeat};
+
+    #[test]
+    fn outcome() {
+        let mut recipies = Recipies::default();
+        let void = ItemID::from("Void");
+        let space = ItemID::from("Space");
+        let trash = ItemID::from("Trash");
+        recipies.add(Recipie::from_str("Void + Void = Space; 0"));
+        recipies.set_trash(vec![void]);
+        assert_eq!(recipies.outcome(void, space, 0.), Outcome::Nothing);
+        assert_eq!(recipies.outcome(void, void, 0.), Outcome::Combines);
+        assert_eq!(recipies.results(void, void), Some(&[space][..]));
+        recipies.combine(void, void);
+        assert_eq!(recipies.outcome(void, void, 0.), Outcome::AlreadyMade);
+        recipies.combine(void, trash);
+        assert_eq!(recipies.outcome(trash, void, 0.), Outcome::Combines);
+    }
+
+    #[test]
+    fn repeat_policy() {
+        assert_eq!(Repeat::parse("once").unwrap(), Repeat::Once);
+        assert_eq!(Repeat::parse("Always").unwrap(), Repeat::Always);
+        assert_eq!(Repeat::parse("cooldown 30").unwrap(), Repeat::Cooldown(30.));
+        assert_eq!(Repeat::parse("cooldown(2.5s)").unwrap(), Repeat::Cooldown(2.5));
+        assert!(Repeat::parse("sometimes").is_err());
+        assert!(Repeat::parse("cooldown -1").is_err());
+        match RecipieType::from_str("Chicken + Chicken = Egg; cooldown 10") {
+            Ok(RecipieType::AddRecipie { rank, repeat, .. }) => {
+                assert_eq!(rank, 0);
+                assert_eq!(repeat, Some(Repeat::Cooldown(10.)));
+            },
+            _ => panic!("should be a recipie with a cooldown"),
+        }
+        match RecipieType::from_str("Egg + Time = Chicken; 3; always") {
+            Ok(RecipieType::AddRecipie { rank, repeat, .. }) => {
+                assert_eq!(rank, 3);
+                assert_eq!(repeat, Some(Repeat::Always));
+            },
+            _ => panic!("should be a recipie that always combines"),
+        }
+        assert!(matches!(RecipieType::from_str("repeat: always"), Ok(RecipieType::DefaultRepeat(Repeat::Always))));
+    }
+
+    #[test]
+    fn repeat_outcome() {
+        let mut recipies = Recipies::default();
+        let chicken = ItemID::from("Chicken");
+        let egg = ItemID::from("Egg");
+        let time = ItemID::from("Time");
+        recipies.insert(chicken, chicken, egg, 0, Some(Repeat::Cooldown(10.)));
+        recipies.insert(egg, time, chicken, 0, None);
+        recipies.combine_at(chicken, chicken, 5.);
+        assert_eq!(recipies.outcome(chicken, chicken, 6.), Outcome::CoolingDown);
+        assert_eq!(recipies.cooldown_left(chicken, chicken, 11.), 4.);
+        assert_eq!(recipies.outcome(chicken, chicken, 15.), Outcome::Combines);
+        recipies.combine_at(egg, time, 0.);
+        assert_eq!(recipies.outcome(egg, time, 0.), Outcome::AlreadyMade);
+        recipies.insert(egg, time, chicken, 0, Some(Repeat::Always));
+        assert_eq!(recipies.outcome(egg, time, 0.), Outcome::Combines);
+    }
+
+    #[test]
+    fn repeat_stays_in_its_file() {
+        let file = ["A + B = C", "repeat: always", "A + C = D; once"].map(|line| RecipieType::from_str(line).unwrap());
+        let repeats = with_file_repeat(file.into()).into_iter()
+            .filter_map(|r| if let RecipieType::AddRecipie { repeat, .. } = r {Some(repeat)} else {None})
+            .collect::<Vec<_>>();
+        assert_eq!(repeats, vec![Some(Repeat::Always), Some(Repeat::Once)]);
+        // a file without one leaves it to the recipie
+        let other = with_file_repeat(vec![RecipieType::from_str("A + A = B").unwrap()]);
+        assert!(matches!(other[..], [RecipieType::AddRecipie { repeat: None, .. }]));
+    }
+
+    #[test]
+    fn cooldown_carries_over() {
+        let mut recipies = Recipies::default();
+        let chicken = ItemID::from("Chicken");
+        let egg = ItemID::from("Egg");
+        recipies.insert(chicken, chicken, egg, 0, Some(Repeat::Cooldown(30.)));
+        recipies.combine_at(chicken, chicken, 100.);
+        let left = recipies.cooling_down(110.);
+        assert_eq!(left, vec![((chicken, chicken), 20.)]);
+        let mut next = Recipies::default();
+        next.set_made(chicken, chicken);
+        next.set_cooldown(chicken, chicken, 20., 0.);
+        next.insert(chicken, chicken, egg, 0, Some(Repeat::Cooldown(30.)));
+        assert_eq!(next.outcome(chicken, chicken, 19.), Outcome::CoolingDown);
+        assert_eq!(next.outcome(chicken, chicken, 20.), Outcome::Combines);
+    }
+
     #[test]
     fn item_item_test() {
         let mut recipies = Recipies::default();
@@ -151,8 +257,44 @@ mod test {
 
 #[derive(Debug, Default)]
 pub struct Recipies{
-    all: HashMap<ItemID, HashMap<ItemID, (Vec<ItemID>, u16)>>,
+    all: HashMap<ItemID, HashMap<ItemID, (Vec<ItemID>, u16, Option<Repeat>)>>,
     made: HashSet<(ItemID, ItemID)>,
+    /// when each pair that was made can be made again, in seconds since startup
+    ready_at: HashMap<(ItemID, ItemID), f64>,
+}
+
+/// if a pair that was made before can be combined again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Repeat {
+    Once,
+    Always,
+    /// seconds to wait after the last combine
+    Cooldown(f32),
+}
+
+impl Default for Repeat {
+    fn default() -> Self {
+        Repeat::Once
+    }
+}
+
+impl Repeat {
+    /// `once`, `always` or `cooldown N` with N in seconds
+    pub fn parse(str: &str) -> Result<Repeat, GameError> {
+        let word = str.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+        match word.as_str() {
+            "once" => return Ok(Repeat::Once),
+            "always" => return Ok(Repeat::Always),
+            _ => {},
+        }
+        let bad = || GameError::BadRepeat(str.trim().to_string());
+        let secs = word.strip_prefix("cooldown").ok_or_else(bad)?;
+        let secs = secs.trim_start_matches('(').trim_end_matches(')').trim_end_matches('s');
+        match secs.parse::<f32>() {
+            Ok(secs) if secs.is_finite() && secs >= 0. => Ok(Repeat::Cooldown(secs)),
+            _ => Err(bad()),
+        }
+    }
 }
 
 impl Recipies {
@@ -175,22 +317,7 @@ impl Recipies {
     #[inline(always)]
     pub fn add(&mut self, recipie: Recipie)
     {
-        self.insert(recipie.item_1, recipie.item_2, recipie.result, recipie.priority);
-    }
-
-    pub fn save(&self) {
-        use std::io::prelude::*;
-        let mut file = if let Ok(f) = std::fs::OpenOptions::new().create(true).write(true).open("./assets/made.sav") {
-            f
-        } else {
-            error!("Failed to create file");
-            return;
-        };
-        for item in self.made.iter() {
-            if let Ok(d) = ron::to_string(item) {
-                let _ = writeln!(&mut file,"{}", d);
-            }
-        }
+        self.insert(recipie.item_1, recipie.item_2, recipie.result, recipie.priority, None);
     }
 
     pub fn load<P>(&mut self, path: P) -> Result<(), GameError>
@@ -202,18 +329,19 @@ impl Recipies {
         Ok(())
     }
 
-    fn insert(&mut self, item1: ItemID, item2: ItemID, makes: ItemID, rank: u16) {
+    fn insert(&mut self, item1: ItemID, item2: ItemID, makes: ItemID, rank: u16, repeat: Option<Repeat>) {
         let (item1, item2) = item1.first(item2);
         if !self.all.contains_key(&item1) {self.all.insert(item1, HashMap::default());};
         if let Some(out) = self.all.get_mut(&item1) {
             if let Some(inn) = out.get_mut(&item2) {
                 if inn.1 > rank {
-                    out.insert(item2, (vec![makes], rank));
+                    out.insert(item2, (vec![makes], rank, repeat));
                 } else if inn.1 == rank {
                     inn.0.push(makes);
+                    if repeat.is_some() {inn.2 = repeat;}
                 }
             } else {
-                out.insert(item2, (vec![makes], rank));
+                out.insert(item2, (vec![makes], rank, repeat));
             }
         }
     } 
@@ -229,25 +357,111 @@ impl Recipies {
         None
     }
 
+    /// combine and remember when, `now` is in seconds since startup
+    pub fn combine_at(&mut self, item1: ItemID, item2: ItemID, now: f64) -> Option<Vec<ItemID>> {
+        let res = self.combine(item1, item2)?;
+        if let Repeat::Cooldown(secs) = self.repeat(item1, item2) {
+            self.ready_at.insert(item1.first(item2), now + secs as f64);
+        }
+        Some(res)
+    }
+
     pub fn set_trash(&mut self, items: Vec<ItemID>) {
         let trash = ItemID::from("Trash");
         for item in items {
-            self.insert(item, trash, trash, u16::MAX);
+            self.insert(item, trash, trash, u16::MAX, Some(Repeat::Always));
         }
     }
 
     pub fn check_combine(&self, item1: ItemID, item2: ItemID) -> bool {
+        self.results(item1, item2).is_some()
+    }
+
+    /// what the pair makes without marking it as made
+    pub fn results(&self, item1: ItemID, item2: ItemID) -> Option<&[ItemID]> {
         let (item1, item2) = item1.first(item2);
-        if let Some(recipies) = self.all.get(&item1) {
-            recipies.get(&item2).is_some()
-        } else {
-            false
+        self.all.get(&item1)
+            .and_then(|recipies| recipies.get(&item2))
+            .map(|(makes, _, _)| makes.as_slice())
+    }
+
+    /// every pair with `item` in it that has been made and what it made
+    pub fn made_with(&self, item: ItemID) -> Vec<((ItemID, ItemID), Vec<ItemID>)> {
+        self.made.iter()
+            .filter(|(item1, item2)| *item1 == item || *item2 == item)
+            .filter_map(|(item1, item2)| Some(((*item1, *item2), self.results(*item1, *item2)?.to_vec())))
+            .collect()
+    }
+
+    /// what dropping `item1` onto `item2` would do, anything can always go in the trash
+    pub fn outcome(&self, item1: ItemID, item2: ItemID, now: f64) -> Outcome {
+        let trash = ItemID::new("Trash");
+        if !self.check_combine(item1, item2) {return Outcome::Nothing;}
+        if item1 == trash || item2 == trash || !self.has_made(item1, item2) {return Outcome::Combines;}
+        match self.repeat(item1, item2) {
+            Repeat::Once => Outcome::AlreadyMade,
+            Repeat::Always => Outcome::Combines,
+            Repeat::Cooldown(_) if self.cooldown_left(item1, item2, now) > 0. => Outcome::CoolingDown,
+            Repeat::Cooldown(_) => Outcome::Combines,
         }
     }
 
+    /// the pair's own policy, or only once if neither it nor its file set one
+    pub fn repeat(&self, item1: ItemID, item2: ItemID) -> Repeat {
+        let (item1, item2) = item1.first(item2);
+        self.all.get(&item1)
+            .and_then(|recipies| recipies.get(&item2))
+            .and_then(|(_, _, repeat)| *repeat)
+            .unwrap_or_default()
+    }
+
+    /// seconds until the pair can be combined again
+    pub fn cooldown_left(&self, item1: ItemID, item2: ItemID, now: f64) -> f32 {
+        if !matches!(self.repeat(item1, item2), Repeat::Cooldown(_)) {return 0.;}
+        let at = if let Some(at) = self.ready_at.get(&item1.first(item2)) {*at} else {return 0.;};
+        (at - now).max(0.) as f32
+    }
+
+    /// every pair still cooling down and the seconds it has left, kept in the save
+    pub fn cooling_down(&self, now: f64) -> Vec<((ItemID, ItemID), f32)> {
+        self.ready_at.iter()
+            .map(|(pair, at)| (*pair, (at - now).max(0.) as f32))
+            .filter(|(_, left)| *left > 0.)
+            .collect()
+    }
+
+    /// puts back a cooldown from the save, the recipies don't have to be loaded yet
+    pub fn set_cooldown(&mut self, item1: ItemID, item2: ItemID, left: f32, now: f64) {
+        self.ready_at.insert(item1.first(item2), now + left as f64);
+    }
+
+    /// every pair that has been combined
+    pub fn made(&self) -> impl Iterator<Item = &(ItemID, ItemID)> {
+        self.made.iter()
+    }
+
     pub fn has_made(&self, item1: ItemID, item2: ItemID) -> bool {
         self.made.contains(&item1.first(item2))
     }
+
+    pub fn set_made(&mut self, item1: ItemID, item2: ItemID) {
+        self.made.insert(item1.first(item2));
+    }
+
+    pub fn forget_made(&mut self, item1: ItemID, item2: ItemID) {
+        self.made.remove(&item1.first(item2));
+        self.ready_at.remove(&item1.first(item2));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Combines,
+    /// the pair was made before and can only be made once
+    AlreadyMade,
+    /// the pair was made too recently
+    CoolingDown,
+    Nothing,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -298,28 +512,53 @@ fn combine(
     mut recipies: ResMut<Recipies>,
     query: Query<(&ItemID, &Transform)>,
     mut commands: Commands,
-    res: Res<Audio>,
+    // missing when running headless
+    res: Option<Res<Audio>>,
     made: Res<MadeSound>,
+    mut history: ResMut<History>,
+    (time, style): (Res<Time>, Res<notice::NoticeStyle>),
+    mut notices: EventWriter<ItemNotice>,
 ) {
+    let now = time.seconds_since_startup();
     let trash = ItemID::new("Trash");
     let mut send = Vec::new();
+    // an item can only be used up once a frame
+    let mut used = HashSet::new();
     for event in set.p0().iter() {
         if let ItemEvent::CheckCombine(item1_e, item2_e) = event {
+            if used.contains(item1_e) || used.contains(item2_e) {continue;}
             let (item1, t1) = if let Ok(i) = query.get(*item1_e) {i} else {continue;};
-            let (item2, _) = if let Ok(i) = query.get(*item2_e) {i} else {continue;};
-            if recipies.has_made(*item1, *item2) && item1.id() != trash.id() && item2.id() != trash.id() {
-                res.play(made.0.clone());
+            let (item2, t2) = if let Ok(i) = query.get(*item2_e) {i} else {continue;};
+            let new = !recipies.has_made(*item1, *item2);
+            let reason = match recipies.outcome(*item1, *item2, now) {
+                Outcome::AlreadyMade => Some("Already made".to_string()),
+                Outcome::CoolingDown => Some(format!("Ready in {:.0}s", recipies.cooldown_left(*item1, *item2, now).ceil())),
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                if let Some(res) = &res {res.play(made.0.clone());}
+                notice::spawn_notice(&mut commands, &style, reason, t2.translation);
                 continue;
             }
-            if let Some(r) = recipies.combine(*item1, *item2) {
-                for r in r {
-                    use rand::Rng;
-                    let x = rand::thread_rng().gen_range(-50.0..50.0);
-                    let y = rand::thread_rng().gen_range(-50.0..50.0);
-                    send.push(ItemEvent::SpawnAt(r, Vec3::new(x + t1.translation.x, y + t1.translation.y, 0.0)));
+            if let Some(r) = recipies.combine_at(*item1, *item2, now) {
+                // the trash stays where it is and takes everything put in it
+                let kept = if *item2 == trash {Some(*item2_e)} else if *item1 == trash {Some(*item1_e)} else {None};
+                for (e, id, t) in [(*item1_e, item1, t1), (*item2_e, item2, t2)] {
+                    if Some(e) == kept {continue;}
+                    history.record(Change::Despawned(e, *id, t.translation));
+                    send.push(ItemEvent::Despawn(e));
+                    used.insert(e);
+                }
+                if new {
+                    history.record(Change::Made(*item1, *item2));
+                }
+                if kept.is_none() {
+                    // placed in the free spots closest to where they were made
+                    for r in r.iter() {
+                        send.push(ItemEvent::PlayerSpawnAt(*r, t1.translation.truncate().extend(0.0)));
+                    }
                 }
-                commands.entity(*item1_e).despawn_recursive();
-                commands.entity(*item2_e).despawn_recursive();
+                notices.send(ItemNotice::Combined(*item1, *item2, r, t1.translation.truncate()));
             }
         }
     }
@@ -334,48 +573,61 @@ enum RecipieType {
         item2: ItemID, 
         makes: ItemID,
         rank: u16,
+        repeat: Option<Repeat>,
     },
-    AddTagRecipie {
-        item: ItemID,
-        has: Tag,
+    MatchRecipie {
+        first: Matcher,
+        second: Matcher,
         makes: ItemID,
         rank: u16,
+        repeat: Option<Repeat>,
     },
-    MakeTagRecipie {
-        item1_has: Tag,
-        item2_has: Tag,
-        makes: ItemID,
-        rank: u16,
-    }
+    /// `repeat: <policy>` sets the policy for every recipie in the same file without one
+    DefaultRepeat(Repeat),
+}
+
+/// gives the file's `repeat:` to its recipies without their own, so it never reaches other files or mods
+fn with_file_repeat(found: Vec<RecipieType>) -> Vec<RecipieType> {
+    let file = found.iter().rev().find_map(|r| if let RecipieType::DefaultRepeat(repeat) = r {Some(*repeat)} else {None});
+    found.into_iter().filter_map(|r| match r {
+        RecipieType::DefaultRepeat(_) => None,
+        RecipieType::AddRecipie { item1, item2, makes, rank, repeat } =>
+            Some(RecipieType::AddRecipie { item1, item2, makes, rank, repeat: repeat.or(file) }),
+        RecipieType::MatchRecipie { first, second, makes, rank, repeat } =>
+            Some(RecipieType::MatchRecipie { first, second, makes, rank, repeat: repeat.or(file) }),
+    }).collect()
 }
 
 impl RecipieType {
     fn from_str(str: &str) -> Result<RecipieType, GameError> {
+        if let Some(policy) = str.trim().strip_prefix("repeat:") {
+            return Ok(RecipieType::DefaultRepeat(Repeat::parse(policy)?));
+        }
         let mut chars = str.chars();
-        let first = extract_word(&mut chars, '+');
-        let second = extract_word(&mut chars, '=');
+        let first = Matcher::parse(&extract_word(&mut chars, '+'))?;
+        let second = Matcher::parse(&extract_word(&mut chars, '='))?;
         let makes = ItemID::from(extract_word(&mut chars, ';'));
-        let first = if first.starts_with('$') {State::Tag(Tag::from_str(&first[1..])?)} else {State::Item(ItemID::from(first))};
-        let second = if second.starts_with('$') {State::Tag(Tag::from_str(&second[1..])?)} else {State::Item(ItemID::from(second))};
-        let rank = extract_word(&mut chars, '\n').parse().unwrap_or_else(|_| {
-            match (first, second) {
-                (State::Item(_), State::Item(_)) => {0},
-                (State::Item(_), State::Tag(_)) |
-                (State::Tag(_), State::Item(_)) => {1},
-                (State::Tag(_), State::Tag(_)) => {2},
+        // after the result comes an optional rank and repeat policy in either order
+        let mut rank = None;
+        let mut repeat = None;
+        for part in extract_word(&mut chars, '\n').split(';').filter(|p| !p.is_empty()) {
+            if let Ok(r) = part.parse() {
+                rank = Some(r);
+            } else {
+                repeat = Some(Repeat::parse(part)?);
+            }
+        }
+        let rank = rank.unwrap_or_else(|| {
+            match (first.is_item(), second.is_item()) {
+                (true, true) => {0},
+                (true, false) |
+                (false, true) => {1},
+                (false, false) => {2},
             }
         });
         match (first, second) {
-            (State::Item(item1), State::Item(item2)) => Ok(RecipieType::AddRecipie { item1, item2, makes, rank}),
-            (State::Item(item), State::Tag(has)) |
-            (State::Tag(has), State::Item(item)) => Ok(RecipieType::AddTagRecipie { item, has, makes, rank}),
-            (State::Tag(item1_has), State::Tag(item2_has)) => Ok(RecipieType::MakeTagRecipie { item1_has, item2_has, makes, rank}),
+            (Matcher::Item(item1), Matcher::Item(item2)) => Ok(RecipieType::AddRecipie { item1, item2, makes, rank, repeat }),
+            (first, second) => Ok(RecipieType::MatchRecipie { first, second, makes, rank, repeat }),
         }
     }
 }
-
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum State {
-    Tag(Tag),
-    Item(ItemID),
-}
\ No newline at end of file
@@ -0,0 +1,160 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use super::Matcher;
+
+    #[test]
+    fn parse() {
+        let apple = ItemID::from("Apple");
+        let fruit = Tag::new("fruit").unwrap();
+        let cooked = Tag::new("cooked").unwrap();
+        assert_eq!(Matcher::parse("Apple").unwrap(), Matcher::Item(apple));
+        assert_eq!(Matcher::parse("$fruit").unwrap(), Matcher::Tag(fruit));
+        assert_eq!(
+            Matcher::parse("$fruit&!$cooked|Apple").unwrap(),
+            Matcher::Or(
+                Box::new(Matcher::And(Box::new(Matcher::Tag(fruit)), Box::new(Matcher::Not(Box::new(Matcher::Tag(cooked)))))),
+                Box::new(Matcher::Item(apple)),
+            )
+        );
+        assert_eq!(
+            Matcher::parse("$fruit&($cooked|Apple)").unwrap(),
+            Matcher::And(
+                Box::new(Matcher::Tag(fruit)),
+                Box::new(Matcher::Or(Box::new(Matcher::Tag(cooked)), Box::new(Matcher::Item(apple)))),
+            )
+        );
+        assert!(Matcher::parse("").is_err());
+        assert!(Matcher::parse("($fruit").is_err());
+        assert!(Matcher::parse("$fruit&").is_err());
+        assert!(Matcher::parse("$fruit)").is_err());
+    }
+
+    #[test]
+    fn matches() {
+        let mut registry = TagRegistry::default();
+        let mut tags = Tags::default();
+        tags.insert(registry.tag("food/perishable").unwrap());
+        let apple = ItemID::from("Apple");
+        let stone = ItemID::from("Stone");
+        let rule = Matcher::parse("$food&!$cooked").unwrap();
+        assert!(rule.matches(&apple, &tags, &registry));
+        tags.insert(registry.tag("cooked").unwrap());
+        assert!(!rule.matches(&apple, &tags, &registry));
+        let rule = Matcher::parse("Stone|$metal").unwrap();
+        assert!(rule.matches(&stone, &Tags::default(), &registry));
+        assert!(!rule.matches(&apple, &tags, &registry));
+    }
+}
+
+/// one side of a recipie;
+/// `$tag` matches a tag, a bare name matches an item,
+/// `!` `&` and `|` bind in that order and `(` `)` group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Matcher {
+    Item(ItemID),
+    Tag(Tag),
+    Not(Box<Matcher>),
+    And(Box<Matcher>, Box<Matcher>),
+    Or(Box<Matcher>, Box<Matcher>),
+}
+
+impl Matcher {
+    pub fn parse(str: &str) -> Result<Matcher, GameError> {
+        let mut chars = str.chars().peekable();
+        let matcher = parse_or(&mut chars, str)?;
+        skip_space(&mut chars);
+        if chars.peek().is_some() {
+            return Err(GameError::BadExpression(str.to_string()));
+        }
+        Ok(matcher)
+    }
+
+    pub fn matches(&self, id: &ItemID, tags: &Tags, registry: &TagRegistry) -> bool {
+        match self {
+            Matcher::Item(item) => item == id,
+            Matcher::Tag(tag) => tags.has(tag, registry),
+            Matcher::Not(inner) => !inner.matches(id, tags, registry),
+            Matcher::And(a, b) => a.matches(id, tags, registry) && b.matches(id, tags, registry),
+            Matcher::Or(a, b) => a.matches(id, tags, registry) || b.matches(id, tags, registry),
+        }
+    }
+
+    /// swaps any renamed item for the item that now owns its id
+    pub fn resolve(&mut self, items: &Items) {
+        match self {
+            Matcher::Item(item) => *item = items.resolve(*item),
+            Matcher::Tag(_) => {},
+            Matcher::Not(inner) => inner.resolve(items),
+            Matcher::And(a, b) |
+            Matcher::Or(a, b) => {
+                a.resolve(items);
+                b.resolve(items);
+            },
+        }
+    }
+
+    pub fn is_item(&self) -> bool {
+        matches!(self, Matcher::Item(_))
+    }
+}
+
+fn skip_space(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn parse_or(chars: &mut Peekable<Chars>, src: &str) -> Result<Matcher, GameError> {
+    let mut left = parse_and(chars, src)?;
+    skip_space(chars);
+    while chars.next_if_eq(&'|').is_some() {
+        let right = parse_and(chars, src)?;
+        left = Matcher::Or(Box::new(left), Box::new(right));
+        skip_space(chars);
+    }
+    Ok(left)
+}
+
+fn parse_and(chars: &mut Peekable<Chars>, src: &str) -> Result<Matcher, GameError> {
+    let mut left = parse_not(chars, src)?;
+    skip_space(chars);
+    while chars.next_if_eq(&'&').is_some() {
+        let right = parse_not(chars, src)?;
+        left = Matcher::And(Box::new(left), Box::new(right));
+        skip_space(chars);
+    }
+    Ok(left)
+}
+
+fn parse_not(chars: &mut Peekable<Chars>, src: &str) -> Result<Matcher, GameError> {
+    skip_space(chars);
+    if chars.next_if_eq(&'!').is_some() {
+        return Ok(Matcher::Not(Box::new(parse_not(chars, src)?)));
+    }
+    if chars.next_if_eq(&'(').is_some() {
+        let inner = parse_or(chars, src)?;
+        skip_space(chars);
+        if chars.next_if_eq(&')').is_none() {
+            return Err(GameError::BadExpression(src.to_string()));
+        }
+        return Ok(inner);
+    }
+    let is_tag = chars.next_if_eq(&'$').is_some();
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(|c| !matches!(c, '|' | '&' | '!' | '(' | ')')) {
+        name.push(c);
+    }
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(GameError::BadExpression(src.to_string()));
+    }
+    if is_tag {
+        Ok(Matcher::Tag(Tag::from_str(name)?))
+    } else {
+        Ok(Matcher::Item(ItemID::from(name)))
+    }
+}
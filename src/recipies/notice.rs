@@ -0,0 +1,56 @@
+use crate::{prelude::*, item::layers::Layer};
+
+/// how long a notice stays on the board
+const NOTICE_TIME: f32 = 1.5;
+/// how far a notice floats up before it is gone
+const NOTICE_RISE: f32 = 40.;
+
+/// text on the board saying why a combine was refused
+#[derive(Component)]
+pub struct Notice(f32);
+
+pub struct NoticeStyle(TextStyle);
+
+impl FromWorld for NoticeStyle {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        NoticeStyle(TextStyle {
+            font: asset_server.load("Font.ttf"),
+            font_size: 20.,
+            color: Color::WHITE,
+        })
+    }
+}
+
+pub fn spawn_notice(commands: &mut Commands, style: &NoticeStyle, value: String, at: Vec3) {
+    commands.spawn_bundle(Text2dBundle {
+        text: Text {
+            sections: vec![TextSection { style: style.0.clone(), value }],
+            alignment: TextAlignment { vertical: VerticalAlign::Center, horizontal: HorizontalAlign::Center },
+        },
+        transform: Transform::from_xyz(at.x, at.y, Layer::Effects.base()),
+        ..Default::default()
+    })
+    .insert(Notice(0.))
+    .insert(Name::new("Notice"));
+}
+
+/// notices float up and fade away
+pub fn fade_notices(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut notices: Query<(Entity, &mut Notice, &mut Transform, &mut Text)>,
+) {
+    for (e, mut notice, mut t, mut text) in notices.iter_mut() {
+        notice.0 += time.delta_seconds();
+        if notice.0 >= NOTICE_TIME {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        t.translation.y += NOTICE_RISE / NOTICE_TIME * time.delta_seconds();
+        let alpha = 1. - notice.0 / NOTICE_TIME;
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{prelude::*, item::{physics::Seleced, spatial::SpatialIndex, config::ItemConfig, layers::part}, ui::UiIcons};
+
+use super::{Recipies, Outcome};
+
+/// the highlight on the item a held item would be dropped on
+#[derive(Debug, Default)]
+pub struct Preview {
+    marker: Option<(Entity, Entity, Outcome)>,
+}
+
+/// on the highlight and result ghosts so they can be told apart from the items they sit on
+#[derive(Component)]
+pub struct PreviewMarker;
+
+fn outcome_color(outcome: Outcome) -> Color {
+    match outcome {
+        Outcome::Combines => Color::rgba(0.2, 0.9, 0.3, 0.9),
+        Outcome::AlreadyMade |
+        Outcome::CoolingDown => Color::rgba(0.6, 0.6, 0.6, 0.9),
+        Outcome::Nothing => Color::rgba(0.9, 0.2, 0.2, 0.9),
+    }
+}
+
+/// shows what dropping the held item would do before it is dropped
+pub fn combine_preview(
+    mut commands: Commands,
+    mut preview: ResMut<Preview>,
+    held: Res<Seleced>,
+    recipies: Res<Recipies>,
+    items: Res<Items>,
+    index: Res<SpatialIndex>,
+    board: Query<(&ItemID, &Transform, &Size)>,
+    markers: Query<(), With<PreviewMarker>>,
+    icons: Res<UiIcons>,
+    config: Res<ItemConfig>,
+    time: Res<Time>,
+) {
+    // the same pair detect_drop would pick
+    let want = held.0.and_then(|e| {
+        let (id, t, size) = board.get(e).ok()?;
+        let (target, _) = *index.overlapping(t.translation.truncate(), size.0, Some(e)).first()?;
+        let (target_id, _, _) = board.get(target).ok()?;
+        Some((target, recipies.outcome(*id, *target_id, time.seconds_since_startup()), *id, *target_id))
+    });
+    if preview.marker.map(|(_, target, outcome)| (target, outcome)) == want.map(|(target, outcome, _, _)| (target, outcome)) {return;}
+    if let Some((marker, _, _)) = preview.marker.take() {
+        // gone already if the item it was on was despawned
+        if markers.contains(marker) {
+            commands.entity(marker).despawn_recursive();
+        }
+    }
+    let (target, outcome, item1, item2) = if let Some(w) = want {w} else {return;};
+    let (_, _, size) = if let Ok(t) = board.get(target) {t} else {return;};
+    let marker = commands.spawn_bundle(SpriteBundle {
+        sprite: Sprite { color: outcome_color(outcome), custom_size: Some(size.0 * 1.3), ..Default::default() },
+        texture: icons.get("item_frame"),
+        transform: Transform::from_translation(Vec3::Z * part::PREVIEW),
+        ..Default::default()
+    })
+    .insert(PreviewMarker)
+    .with_children(|p| {
+        // a pair that was made before shows what it made above the target
+        if outcome == Outcome::Nothing || !recipies.has_made(item1, item2) || !config.preview_results {return;}
+        let results = recipies.results(item1, item2).unwrap_or_default();
+        let width = config.icon_size.x * 0.6;
+        let left = -(results.len() as f32 - 1.) * width / 2.;
+        for (i, result) in results.iter().enumerate() {
+            p.spawn_bundle(SpriteBundle {
+                sprite: Sprite { color: Color::rgba(1., 1., 1., 0.5), custom_size: Some(config.icon_size * 0.5), ..Default::default() },
+                texture: items.get(result).icon(),
+                // on the marker, so only as far above it as the ghost is above the target
+                transform: Transform::from_xyz(left + i as f32 * width, size.y * 0.8, part::GHOST - part::PREVIEW),
+                ..Default::default()
+            }).insert(PreviewMarker);
+        }
+    })
+    .id();
+    commands.entity(target).add_child(marker);
+    preview.marker = Some((marker, target, outcome));
+}
@@ -0,0 +1,409 @@
+//! rhai scripts from the mod folders, for the rules `.vr` lines can't say
+
+use std::{collections::{HashMap, HashSet}, path::Path, sync::{Arc, Mutex, MutexGuard}};
+
+use bevy::ecs::event::ManualEventReader;
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+
+use crate::{prelude::*, item::{config::ItemConfig, layout::board_area}, recipies::Recipies, ui::ItemSpaceItem};
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use rhai::Dynamic;
+    use crate::item::ItemID;
+    use super::{Scripts, MAX_REQUESTS};
+
+    #[test]
+    fn finds_hooks() {
+        let mut scripts = Scripts::new();
+        scripts.add("test", "fn on_tick(dt) { helper() } fn helper() {} fn on_found(name) {}").unwrap();
+        assert!(scripts.has_hook("on_tick"));
+        assert!(scripts.has_hook("on_found"));
+        assert!(!scripts.has_hook("helper"));
+        assert!(!scripts.has_hook("on_combine"));
+    }
+
+    #[test]
+    fn sandboxed() {
+        let mut scripts = Scripts::new();
+        assert!(scripts.add("eval", r#"fn on_tick(dt) { eval("1 + 1") }"#).is_err());
+        scripts.add("loop", "fn on_tick(dt) { loop {} }").unwrap();
+        // stopped by the operation limit and never called again
+        scripts.call("on_tick", vec![Dynamic::from(0.1_f64)]);
+        assert!(!scripts.has_hook("on_tick"));
+    }
+
+    #[test]
+    fn state_is_per_script() {
+        let mut scripts = Scripts::new();
+        scripts.add("a", r#"fn on_tick(dt) { set("ticks", get("ticks", 0) + 1) }"#).unwrap();
+        scripts.add("b", r#"fn on_tick(dt) { set("ticks", get("ticks", 0) + 10) }"#).unwrap();
+        scripts.call("on_tick", vec![Dynamic::from(0.1_f64)]);
+        scripts.call("on_tick", vec![Dynamic::from(0.1_f64)]);
+        let shared = scripts.shared();
+        assert_eq!(shared.state.get(&(0, "ticks".to_string())).and_then(|d| d.as_int().ok()), Some(2));
+        assert_eq!(shared.state.get(&(1, "ticks".to_string())).and_then(|d| d.as_int().ok()), Some(20));
+    }
+
+    #[test]
+    fn too_many_requests_are_all_dropped() {
+        let mut scripts = Scripts::new();
+        scripts.shared().board.push((Entity::from_raw(1), ItemID::from("Void"), Vec2::ZERO));
+        let greedy = format!("fn on_tick(dt) {{ let e = board()[0].id; for i in 0..{} {{ despawn(e) }} }}", MAX_REQUESTS + 1);
+        scripts.add("greedy", &greedy).unwrap();
+        scripts.add("modest", "fn on_tick(dt) { despawn(board()[0].id) }").unwrap();
+        // the cap is for each hook, the one after still gets its turn
+        scripts.call("on_tick", vec![Dynamic::from(0.1_f64)]);
+        assert_eq!(scripts.shared().requests.len(), 1);
+        scripts.call("on_tick", vec![Dynamic::from(0.1_f64)]);
+        assert_eq!(scripts.shared().requests.len(), 2);
+    }
+}
+
+/// the functions a script can have the game call
+const HOOKS: [&str; 4] = ["on_spawn", "on_found", "on_combine", "on_tick"];
+/// the most spawns and despawns one hook can ask for
+const MAX_REQUESTS: usize = 64;
+/// stops a hook that would never finish
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// runs the hooks of every `.rhai` file under the `AssetDir`
+pub struct ScriptPlugin;
+impl Plugin for ScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Scripts>();
+        app.add_system_to_stage(CoreStage::PostUpdate, run_hooks.exclusive_system());
+    }
+}
+
+/// the compiled scripts and the engine they run in
+pub struct Scripts {
+    engine: Engine,
+    scripts: Vec<Script>,
+    shared: Arc<Mutex<Shared>>,
+    notices: ManualEventReader<ItemNotice>,
+}
+
+struct Script {
+    name: String,
+    ast: AST,
+    /// hooks it has that have not failed
+    hooks: HashSet<String>,
+}
+
+/// what the script functions can see, `Items`, `Recipies` and `TagRegistry` are only lent while hooks run
+#[derive(Default)]
+struct Shared {
+    items: Option<Items>,
+    recipies: Option<Recipies>,
+    registry: Option<TagRegistry>,
+    board: Vec<(Entity, ItemID, Vec2)>,
+    area: (Vec2, Vec2),
+    requests: Vec<ItemEvent>,
+    /// where the running hook's requests start in `requests`
+    started: usize,
+    /// the running hook asked for too much, none of what it asked for is done
+    over: bool,
+    /// `get` and `set` values, kept apart for each script
+    state: HashMap<(usize, String), Dynamic>,
+    running: usize,
+}
+
+impl Shared {
+    fn id(&self, name: &str) -> Option<ItemID> {
+        let items = self.items.as_ref()?;
+        let id = items.resolve(ItemID::from(name));
+        if items.contains(&id) {Some(id)} else {None}
+    }
+
+    fn name(&self, id: &ItemID) -> String {
+        self.items.as_ref().map_or(String::new(), |items| items.get(id).name().to_string())
+    }
+
+    fn request(&mut self, event: ItemEvent) {
+        if self.requests.len() - self.started >= MAX_REQUESTS {
+            self.over = true;
+            return;
+        }
+        self.requests.push(event);
+    }
+
+    /// a new hook call with none of its own requests yet
+    fn start(&mut self, script: usize) {
+        self.running = script;
+        self.started = self.requests.len();
+        self.over = false;
+    }
+
+    /// throws away everything the running hook asked for so a despawn is never kept without its spawns
+    fn undo_requests(&mut self) {
+        let started = self.started;
+        self.requests.truncate(started);
+    }
+}
+
+impl FromWorld for Scripts {
+    fn from_world(world: &mut World) -> Self {
+        let mut scripts = Scripts::new();
+        if let Err(e) = scripts.load_folder(&crate::AssetDir::of(world).0) {
+            error!("{}", e);
+        }
+        scripts
+    }
+}
+
+impl Scripts {
+    /// no scripts yet, just the engine and the functions scripts can call
+    pub fn new() -> Scripts {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        Scripts { engine: sandboxed(&shared), scripts: Vec::new(), shared, notices: ManualEventReader::default() }
+    }
+
+    pub fn load_folder<P>(&mut self, path: P) -> Result<(), GameError> where P: AsRef<Path> {
+        use std::fs;
+        for file in fs::read_dir(path)? {
+            let file = match file {Ok(f) => {f}, Err(e) => {error!("file: {}", e); continue;},};
+            if file.metadata()?.is_dir() {
+                if let Err(e) = self.load_folder(file.path()) {
+                    error!("Rec: {}", e);
+                };
+                continue;
+            }
+            if file.path().extension().map_or(true, |ext| ext != "rhai") {continue;}
+            let source = fs::read_to_string(file.path())?;
+            if let Err(e) = self.add(&file.path().display().to_string(), &source) {error!("{}", e)};
+        }
+        Ok(())
+    }
+
+    /// compiles `source`, nothing outside its functions is ever run
+    pub fn add(&mut self, name: &str, source: &str) -> Result<(), GameError> {
+        let ast = self.engine.compile(source).map_err(|e| GameError::ScriptError(name.to_string(), e.to_string()))?;
+        let hooks = ast.iter_functions()
+            .map(|f| f.name.to_string())
+            .filter(|f| HOOKS.contains(&f.as_str()))
+            .collect();
+        info!("loaded script {}", name);
+        self.scripts.push(Script { name: name.to_string(), ast, hooks });
+        Ok(())
+    }
+
+    pub fn has_hook(&self, hook: &str) -> bool {
+        self.scripts.iter().any(|s| s.hooks.contains(hook))
+    }
+
+    /// calls `hook` in every script that has it, a hook that fails is turned off
+    pub fn call(&mut self, hook: &str, args: Vec<Dynamic>) {
+        for (i, script) in self.scripts.iter_mut().enumerate() {
+            if !script.hooks.contains(hook) {continue;}
+            lock(&self.shared).start(i);
+            let res = self.engine.call_fn_raw(&mut Scope::new(), &script.ast, false, false, hook, None, args.clone());
+            let mut shared = lock(&self.shared);
+            if let Err(e) = res {
+                error!("{}", GameError::ScriptError(format!("{}::{}", script.name, hook), e.to_string()));
+                script.hooks.remove(hook);
+                shared.undo_requests();
+            } else if shared.over {
+                warn!("{}::{} asked for more than {} spawns and despawns, none were done", script.name, hook, MAX_REQUESTS);
+                shared.undo_requests();
+            }
+        }
+    }
+
+    fn shared(&self) -> MutexGuard<Shared> {
+        lock(&self.shared)
+    }
+}
+
+/// a script that panicked mid hook leaves nothing half written worth throwing away
+fn lock(shared: &Mutex<Shared>) -> MutexGuard<Shared> {
+    shared.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// rhai numbers can be ints or floats, positions take either
+fn number(value: &Dynamic) -> f32 {
+    value.as_float().map(|f| f as f32)
+        .or_else(|_| value.as_int().map(|i| i as f32))
+        .unwrap_or(0.)
+}
+
+fn sandboxed(shared: &Arc<Mutex<Shared>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(4096);
+    engine.set_max_array_size(1024);
+    engine.set_max_map_size(1024);
+    // no reading other files or running strings as code
+    engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.on_print(|s| info!("script: {}", s));
+
+    engine.register_fn("log", |msg: &str| info!("script: {}", msg));
+    engine.register_fn("max_requests", || MAX_REQUESTS as i64);
+
+    let s = shared.clone();
+    engine.register_fn("spawn", move |name: &str| {
+        let mut shared = lock(&s);
+        match shared.id(name) {
+            Some(id) => shared.request(ItemEvent::Spawn(id)),
+            None => warn!("script tried to spawn unknown item {}", name),
+        }
+    });
+    let s = shared.clone();
+    engine.register_fn("spawn_at", move |name: &str, x: Dynamic, y: Dynamic| {
+        let mut shared = lock(&s);
+        match shared.id(name) {
+            Some(id) => shared.request(ItemEvent::SpawnAt(id, Vec3::new(number(&x), number(&y), 0.))),
+            None => warn!("script tried to spawn unknown item {}", name),
+        }
+    });
+    let s = shared.clone();
+    engine.register_fn("despawn", move |id: i64| {
+        let mut shared = lock(&s);
+        let e = Entity::from_bits(id as u64);
+        // only what is on the board, not the ui or anything else
+        if shared.board.iter().any(|(other, _, _)| *other == e) {
+            shared.request(ItemEvent::Despawn(e));
+        }
+    });
+
+    let s = shared.clone();
+    engine.register_fn("board", move || {
+        let shared = lock(&s);
+        shared.board.iter().map(|(e, id, at)| {
+            let mut item = Map::new();
+            item.insert("id".into(), Dynamic::from(e.to_bits() as i64));
+            item.insert("name".into(), Dynamic::from(shared.name(id)));
+            item.insert("x".into(), Dynamic::from(at.x as f64));
+            item.insert("y".into(), Dynamic::from(at.y as f64));
+            Dynamic::from(item)
+        }).collect::<Array>()
+    });
+    let s = shared.clone();
+    engine.register_fn("board_area", move || {
+        let (min, max) = lock(&s).area;
+        let mut area = Map::new();
+        area.insert("min_x".into(), Dynamic::from(min.x as f64));
+        area.insert("min_y".into(), Dynamic::from(min.y as f64));
+        area.insert("max_x".into(), Dynamic::from(max.x as f64));
+        area.insert("max_y".into(), Dynamic::from(max.y as f64));
+        area
+    });
+
+    let s = shared.clone();
+    engine.register_fn("has_tag", move |name: &str, tag: &str| {
+        let shared = lock(&s);
+        let tag = if let Ok(tag) = Tag::from_str(tag) {tag} else {return false;};
+        let (items, registry) = if let (Some(i), Some(r)) = (&shared.items, &shared.registry) {(i, r)} else {return false;};
+        shared.id(name).and_then(|id| items.tags(&id)).map_or(false, |tags| tags.has(&tag, registry))
+    });
+    let s = shared.clone();
+    engine.register_fn("items_with_tag", move |tag: &str| {
+        let shared = lock(&s);
+        let tag = if let Ok(tag) = Tag::from_str(tag) {tag} else {return Array::new();};
+        let (items, registry) = if let (Some(i), Some(r)) = (&shared.items, &shared.registry) {(i, r)} else {return Array::new();};
+        items.with_tag(&tag, registry).iter().map(|id| Dynamic::from(shared.name(id))).collect()
+    });
+    let s = shared.clone();
+    engine.register_fn("is_found", move |name: &str| {
+        let shared = lock(&s);
+        let (id, items) = if let (Some(id), Some(items)) = (shared.id(name), &shared.items) {(id, items)} else {return false;};
+        items.found().contains(&id)
+    });
+
+    let s = shared.clone();
+    engine.register_fn("can_combine", move |a: &str, b: &str| {
+        let shared = lock(&s);
+        let (a, b) = if let (Some(a), Some(b)) = (shared.id(a), shared.id(b)) {(a, b)} else {return false;};
+        shared.recipies.as_ref().map_or(false, |r| r.check_combine(a, b))
+    });
+    let s = shared.clone();
+    engine.register_fn("results", move |a: &str, b: &str| {
+        let shared = lock(&s);
+        let (a, b) = if let (Some(a), Some(b)) = (shared.id(a), shared.id(b)) {(a, b)} else {return Array::new();};
+        let made = shared.recipies.as_ref().and_then(|r| r.results(a, b)).unwrap_or_default();
+        made.iter().map(|id| Dynamic::from(shared.name(id))).collect::<Array>()
+    });
+
+    let s = shared.clone();
+    engine.register_fn("get", move |key: &str, default: Dynamic| {
+        let shared = lock(&s);
+        shared.state.get(&(shared.running, key.to_string())).cloned().unwrap_or(default)
+    });
+    let s = shared.clone();
+    engine.register_fn("set", move |key: &str, value: Dynamic| {
+        let mut shared = lock(&s);
+        let running = shared.running;
+        shared.state.insert((running, key.to_string()), value);
+    });
+    engine
+}
+
+/// turns this frame's notices into hook calls, the scripts borrow the item and recipie data while they run
+fn run_hooks(world: &mut World) {
+    world.resource_scope(|world, mut scripts: Mut<Scripts>| {
+        let mut calls: Vec<(&str, Vec<Dynamic>)> = Vec::new();
+        let scripts = &mut *scripts;
+        let items = world.resource::<Items>();
+        for notice in scripts.notices.iter(world.resource::<Events<ItemNotice>>()) {
+            match notice {
+                ItemNotice::Spawned(e) => {
+                    let (id, at) = if let (Some(id), Some(t)) = (world.get::<ItemID>(*e), world.get::<Transform>(*e)) {(id, t.translation)} else {continue;};
+                    calls.push(("on_spawn", vec![
+                        Dynamic::from(items.get(id).name().to_string()),
+                        Dynamic::from(e.to_bits() as i64),
+                        Dynamic::from(at.x as f64),
+                        Dynamic::from(at.y as f64),
+                    ]));
+                },
+                ItemNotice::Found(id) => calls.push(("on_found", vec![Dynamic::from(items.get(id).name().to_string())])),
+                ItemNotice::Combined(a, b, made, at) => {
+                    let made: Array = made.iter().map(|id| Dynamic::from(items.get(id).name().to_string())).collect();
+                    calls.push(("on_combine", vec![
+                        Dynamic::from(items.get(a).name().to_string()),
+                        Dynamic::from(items.get(b).name().to_string()),
+                        Dynamic::from(made),
+                        Dynamic::from(at.x as f64),
+                        Dynamic::from(at.y as f64),
+                    ]));
+                },
+                ItemNotice::Droped(_) => {},
+            }
+        }
+        calls.push(("on_tick", vec![Dynamic::from(world.resource::<Time>().delta_seconds() as f64)]));
+        calls.retain(|(hook, _)| scripts.has_hook(hook));
+        if calls.is_empty() {return;}
+
+        let board = world.query_filtered::<(Entity, &ItemID, &Transform), (With<Size>, Without<ItemSpaceItem>)>()
+            .iter(world)
+            .map(|(e, id, t)| (e, *id, t.translation.truncate()))
+            .collect();
+        let area = board_area(world.resource::<ItemConfig>().board_size);
+        {
+            let mut shared = scripts.shared();
+            shared.items = world.remove_resource::<Items>();
+            shared.recipies = world.remove_resource::<Recipies>();
+            shared.registry = world.remove_resource::<TagRegistry>();
+            shared.board = board;
+            shared.area = area;
+        }
+        for (hook, args) in calls {
+            scripts.call(hook, args);
+        }
+        let requests = {
+            let mut shared = scripts.shared();
+            if let Some(items) = shared.items.take() {world.insert_resource(items);}
+            if let Some(recipies) = shared.recipies.take() {world.insert_resource(recipies);}
+            if let Some(registry) = shared.registry.take() {world.insert_resource(registry);}
+            shared.board.clear();
+            std::mem::take(&mut shared.requests)
+        };
+        let mut events = world.resource_mut::<Events<ItemEvent>>();
+        for request in requests {
+            events.send(request);
+        }
+    });
+}
@@ -1,148 +1,206 @@
-use std::{path::PathBuf, str::Chars};
+use std::path::PathBuf;
 
-use bevy::{time::FixedTimestep, window::WindowCloseRequested};
+use bevy::{ecs::event::ManualEventReader, time::FixedTimestep, window::WindowCloseRequested};
 
-use crate::{prelude::*, item::Items, recipies::Recipies, ui};
+use crate::{prelude::*, item::physics::Locked, recipies::Recipies, story::StoryFlags, ui};
+
+mod document;
+mod legacy;
+
+pub use document::{SaveDocument, SAVE_VERSION};
 
 pub struct SaveLoadPlugin;
 
-const SAVE_PATH: &'static str = "./assets/game.sav";
-const OLD_PATH: &'static str = "./assets/game.old";
+const SAVE_FILE: &'static str = "save.ron";
+const OLD_FILE: &'static str = "save.old";
 
 impl Plugin for SaveLoadPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<SaveGame>();
+        app.init_resource::<SaveDir>();
+        app.init_resource::<StoryFlags>();
+        app.init_resource::<ui::Pinned>();
         app.add_system_set(
             SystemSet::new()
             .with_run_criteria(FixedTimestep::step(30.0))
             .with_system(save)
         );
         app.add_system(save_on_quit);
-        app.add_startup_system(load);
+        app.add_system(save_requested);
+        // before any startup system looks at what was found or the story
+        app.add_startup_system_to_stage(StartupStage::PreStartup, load);
+    }
+}
+
+/// writes the save now instead of waiting for the timer
+pub struct SaveGame;
+
+/// the folder every save is read from and written to, tests point it somewhere empty
+#[derive(Debug, Clone)]
+pub struct SaveDir(pub PathBuf);
+
+impl Default for SaveDir {
+    fn default() -> Self {
+        SaveDir(PathBuf::from("./assets"))
+    }
+}
+
+impl SaveDir {
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.0.join(name)
     }
 }
 
+type Board<'w, 's> = Query<'w, 's, (&'static ItemID, &'static Transform, Option<&'static Locked>), Without<ui::ItemSpaceItem>>;
+
 fn save_on_quit(
-    world: Query<(&ItemID, &Transform), Without<ui::ItemSpaceItem>>,
-    items: Res<Items>,
-    mut events: EventReader<WindowCloseRequested>,
-    made: Res<Recipies>,
+    board: Board,
+    (items, made, story, pinned): (Res<Items>, Res<Recipies>, Res<StoryFlags>, Res<ui::Pinned>),
+    time: Res<Time>,
+    mut reader: Local<ManualEventReader<WindowCloseRequested>>,
+    // there is no window to close when running headless
+    events: Option<Res<Events<WindowCloseRequested>>>,
+    dir: Res<SaveDir>,
 ) {
-    for event in events.iter() {
+    let events = if let Some(e) = events {e} else {return;};
+    for event in reader.iter(&events) {
         if event.id.is_primary() {
-            use std::fs;
-            if PathBuf::from(SAVE_PATH).exists() {
-                fs::rename(&SAVE_PATH, &OLD_PATH).expect("can rename");
-            }
-            let mut file = match fs::OpenOptions::new().write(true).create(true).open("./assets/game.sav") {
-            Ok(f) => {f},
-            Err(e) => {
-                if PathBuf::from(OLD_PATH).exists() {
-                    fs::rename(&OLD_PATH, &SAVE_PATH).expect("can rename");
-                }
-                error!("{}",e); return;}
-            };
-            if let Err(e) = save_to_file(&mut file, &world, &items) {
-                if PathBuf::from(OLD_PATH).exists() {
-                    fs::rename(&OLD_PATH, &SAVE_PATH).expect("can rename");
-                }
-                error!("{}",e); return;
-            }
-            made.save();
-            if let Err(e) = items.save_found() {
-                error!("{}", e);
-            }
+            write_save(&document(&board, &items, &made, &story, &pinned, time.seconds_since_startup()), &dir);
         }
     }
 }
 
 fn save(
-    world: Query<(&ItemID, &Transform), Without<ui::ItemSpaceItem>>,
-    items: Res<Items>,
+    board: Board,
+    (items, made, story, pinned): (Res<Items>, Res<Recipies>, Res<StoryFlags>, Res<ui::Pinned>),
+    time: Res<Time>,
+    dir: Res<SaveDir>,
 ) {
-    use std::fs;
-    if PathBuf::from(SAVE_PATH).exists() {
-        fs::rename(&SAVE_PATH, &OLD_PATH).expect("can rename");
-    }
-    let mut file = match fs::OpenOptions::new().write(true).create(true).open(SAVE_PATH) {
-      Ok(f) => {f},
-      Err(e) => {
-        if PathBuf::from(OLD_PATH).exists() {
-            fs::rename(&OLD_PATH, &SAVE_PATH).expect("can rename");
-        }
-        error!("{}",e); return;}
-    };
-    if let Err(e) = save_to_file(&mut file, &world, &items) {
-        if PathBuf::from(OLD_PATH).exists() {
-            fs::rename(&OLD_PATH, &SAVE_PATH).expect("can rename");
-        }
-        error!("{}",e); return;
+    write_save(&document(&board, &items, &made, &story, &pinned, time.seconds_since_startup()), &dir);
+}
+
+fn save_requested(
+    mut events: EventReader<SaveGame>,
+    board: Board,
+    (items, made, story, pinned): (Res<Items>, Res<Recipies>, Res<StoryFlags>, Res<ui::Pinned>),
+    time: Res<Time>,
+    dir: Res<SaveDir>,
+) {
+    if events.iter().count() > 0 {
+        write_save(&document(&board, &items, &made, &story, &pinned, time.seconds_since_startup()), &dir);
     }
 }
 
-fn save_to_file(file: &mut std::fs::File, query: &Query<(&ItemID, &Transform), Without<ui::ItemSpaceItem>>, items: &Items) -> Result<(), GameError> {
-    use std::io::prelude::*;
+/// everything there is to keep right now
+fn document(board: &Board, items: &Items, made: &Recipies, story: &StoryFlags, pinned: &ui::Pinned, now: f64) -> SaveDocument {
     let trash = ItemID::new("Trash");
     let app = ItemID::new("Totally a game");
     let debug = ItemID::new("Debug Item");
-    for (item, at) in query.iter() {
-        if item == &trash {continue;
-        } else if item == &app {continue;
-        } else if item == &debug {continue;
-        } else {
-            let name = items.get(item).name().replace(' ', "_");
-            writeln!(file, "{}:{}",name, at.translation.truncate())?;
+    let mut doc = SaveDocument::new();
+    for (item, at, locked) in board.iter() {
+        if item == &trash || item == &app || item == &debug {continue;}
+        let saved = (items.get(item).name().to_string(), at.translation);
+        if locked.is_some() {doc.locked.push(saved);} else {doc.board.push(saved);}
+    }
+    // anything from a mod that is gone would only be saved as the debug item
+    doc.found = items.found().iter()
+        .filter(|id| items.contains(id))
+        .map(|id| items.get(id).name().to_string())
+        .collect();
+    doc.made = made.made()
+        .filter(|(item1, item2)| items.contains(item1) && items.contains(item2))
+        .map(|(item1, item2)| (items.get(item1).name().to_string(), items.get(item2).name().to_string()))
+        .collect();
+    doc.cooling = made.cooling_down(now).into_iter()
+        .filter(|((item1, item2), _)| items.contains(item1) && items.contains(item2))
+        .map(|((item1, item2), left)| (items.get(&item1).name().to_string(), items.get(&item2).name().to_string(), left))
+        .collect();
+    doc.pinned = pinned.0.iter()
+        .filter(|id| items.contains(id))
+        .map(|id| items.get(id).name().to_string())
+        .collect();
+    doc.story = story.clone();
+    doc
+}
+
+/// false if nothing could be written, the last save is kept as `save.old` until it works
+fn write_save(doc: &SaveDocument, dir: &SaveDir) -> bool {
+    use std::fs;
+    let (save_path, old_path) = (dir.file(SAVE_FILE), dir.file(OLD_FILE));
+    if save_path.exists() {
+        // nothing is written over the last save if it can't be kept
+        if let Err(e) = fs::rename(&save_path, &old_path) {
+            error!("{}", e); return false;
         }
     }
-    Ok(())
+    if let Err(e) = doc.write(&save_path) {
+        if old_path.exists() {
+            if let Err(e) = fs::rename(&old_path, &save_path) {error!("{}", e);}
+        }
+        error!("{}",e); return false;
+    }
+    true
 }
 
+/// reads the save, or the old files the first time, and puts everything back
 fn load(
     mut events: EventWriter<ItemEvent>,
+    mut items: ResMut<Items>,
+    mut made: ResMut<Recipies>,
+    mut story: ResMut<StoryFlags>,
+    mut pinned: ResMut<ui::Pinned>,
+    time: Res<Time>,
+    dir: Res<SaveDir>,
 ) {
-    if let Err(e) = if PathBuf::from(SAVE_PATH).exists() {
-         load_path(&SAVE_PATH, &mut events)
-    } else if PathBuf::from(OLD_PATH).exists() {
-        load_path(&OLD_PATH, &mut events)
-    } else {
-        info!("Failed to find save");
-        Ok(())
-    } {
-        error!("{}", e);
+    let doc = match read_save(&dir, &items) {
+        Some(doc) => doc,
+        None => {info!("Failed to find save"); return;},
+    };
+    for name in doc.found.iter() {
+        let id = items.resolve(ItemID::new(name.as_str()));
+        items.discover(id);
+    }
+    for (item1, item2) in doc.made.iter() {
+        made.set_made(items.resolve(ItemID::new(item1.as_str())), items.resolve(ItemID::new(item2.as_str())));
+    }
+    for (item1, item2, left) in doc.cooling.iter() {
+        let (item1, item2) = (items.resolve(ItemID::new(item1.as_str())), items.resolve(ItemID::new(item2.as_str())));
+        made.set_cooldown(item1, item2, *left, time.seconds_since_startup());
+    }
+    *story = doc.story;
+    pinned.0 = doc.pinned.iter().map(|name| items.resolve(ItemID::new(name.as_str()))).collect();
+    let mut saved: Vec<(String, Vec3, bool)> = doc.board.into_iter().map(|(name, at)| (name, at, false))
+        .chain(doc.locked.into_iter().map(|(name, at)| (name, at, true)))
+        .collect();
+    // spawned bottom first so each lands on top of the last and the draw order comes back
+    saved.sort_by(|(_, a, _), (_, b, _)| a.z.total_cmp(&b.z));
+    for (name, at, locked) in saved {
+        let id = items.resolve(ItemID::new(name.as_str()));
+        // anything saved off the board is moved back onto it by `relocate_lost` once it is spawned
+        events.send(ItemEvent::Restore(id, at.truncate().extend(0.0), locked));
     }
 }
 
-fn load_path(path: &str, events: &mut EventWriter<ItemEvent>) -> Result<(), GameError> {
-    use std::fs;
-    let data = fs::read_to_string(path)?;
-    for line in data.split('\n') {
-        if line.len() < 6 {continue;}
-        let (id, at) = extract_item(line.chars())?;
-        events.send(ItemEvent::SpawnAt(id, at.extend(0.0)));
-    }   
-    Ok(())
-}
-
-fn extract_item(mut chars: Chars) -> Result<(ItemID, Vec2), GameError> {
-    let mut name = String::new();
-    while let Some(c) = chars.next() {
-        if c == ':' {break;}
-        if c == '_' {name.push(' '); continue;}
-        if c.is_whitespace() {continue;}
-        name.push(c);
-    };
-    let mut open = false;
-    let mut x = String::new();
-    let mut y = String::new();
-    let mut is_x = true;
-    while let Some(c) = chars.next() {
-        if c.is_whitespace() {continue;}
-        if c == '[' {open = true; continue;}
-        if c == ']' {break;}
-        if !open {return Err(GameError::WrongChar(c, '['));}
-        if c == ',' {is_x = false; continue;}
-        if is_x {x.push(c)} else {y.push(c)}
+/// falls back to the save before last if the last one can't be read
+fn read_save(dir: &SaveDir, items: &Items) -> Option<SaveDocument> {
+    let mut unread = false;
+    for file in [SAVE_FILE, OLD_FILE] {
+        let path = dir.file(file);
+        if !path.exists() {continue;}
+        match SaveDocument::read(&path) {
+            Ok(doc) => return Some(doc),
+            Err(e) => error!("{}: {}", path.display(), e),
+        }
+        // a save that can't be read, likely from a newer game, is moved aside before
+        // anything else is loaded so the next save can't write over it
+        unread = true;
+        if let Err(e) = std::fs::rename(&path, dir.file(&format!("{}.unread", file))) {error!("{}", e);}
     }
-    let x = x.parse()?;
-    let y = y.parse()?;
-    Ok((ItemID::new(name), Vec2::new(x,y)))
-}
\ No newline at end of file
+    if unread {return None;}
+    let doc = legacy::read(dir, items)?;
+    info!("moving old saves into {}", SAVE_FILE);
+    if write_save(&doc, dir) {
+        legacy::retire(dir);
+    }
+    Some(doc)
+}
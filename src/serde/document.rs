@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use crate::{prelude::*, story::StoryFlags};
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use super::{SaveDocument, SAVE_VERSION};
+
+    #[test]
+    fn round_trip() {
+        let mut doc = SaveDocument::new();
+        doc.board.push(("Void".to_string(), Vec3::new(1., 2., 3.)));
+        doc.locked.push(("Stone".to_string(), Vec3::new(4., 5., 6.)));
+        doc.found.push("Space".to_string());
+        doc.pinned.push("Space".to_string());
+        doc.made.push(("Void".to_string(), "Void".to_string()));
+        doc.cooling.push(("Void".to_string(), "Void".to_string(), 12.5));
+        let str = doc.to_string().unwrap();
+        let back = SaveDocument::from_str(&str).unwrap();
+        assert_eq!(back.version, SAVE_VERSION);
+        assert_eq!(back.board, doc.board);
+        assert_eq!(back.locked, doc.locked);
+        assert_eq!(back.found, doc.found);
+        assert_eq!(back.pinned, doc.pinned);
+        assert_eq!(back.made, doc.made);
+        assert_eq!(back.cooling, doc.cooling);
+    }
+
+    #[test]
+    fn versions() {
+        // anything left out is empty
+        let doc = SaveDocument::from_str(&format!("(version: {})", SAVE_VERSION)).unwrap();
+        assert!(doc.board.is_empty());
+        assert!(SaveDocument::from_str(&format!("(version: {}, board: [], shiny: true)", SAVE_VERSION + 1)).is_err());
+        assert!(SaveDocument::from_str("(board: [])").is_err());
+    }
+}
+
+/// bumped whenever `SaveDocument` changes shape, `upgrade` brings older documents up to date
+pub const SAVE_VERSION: u32 = 1;
+
+/// everything kept between sessions, items are kept by name so they survive changes to the mods
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct SaveDocument {
+    pub version: u32,
+    /// what is on the board and where, z keeps the draw order
+    #[serde(default)]
+    pub board: Vec<(String, Vec3)>,
+    /// the same as `board` for the items locked in place
+    #[serde(default)]
+    pub locked: Vec<(String, Vec3)>,
+    #[serde(default)]
+    pub found: Vec<String>,
+    /// pairs that have been combined
+    #[serde(default)]
+    pub made: Vec<(String, String)>,
+    /// pairs still cooling down and the seconds they had left
+    #[serde(default)]
+    pub cooling: Vec<(String, String, f32)>,
+    /// pinned to the front of the item space, last pinned first
+    #[serde(default)]
+    pub pinned: Vec<String>,
+    #[serde(default)]
+    pub story: StoryFlags,
+}
+
+/// only the version, read first so a newer document is refused before the rest is looked at
+#[derive(serde::Deserialize)]
+struct Version {
+    version: u32,
+}
+
+impl SaveDocument {
+    pub fn new() -> SaveDocument {
+        SaveDocument { version: SAVE_VERSION, ..Default::default() }
+    }
+
+    pub fn from_str(str: &str) -> Result<SaveDocument, GameError> {
+        let Version { version } = ron::from_str(str)?;
+        if version > SAVE_VERSION {
+            return Err(GameError::SaveVersion(version));
+        }
+        let doc: SaveDocument = ron::from_str(str)?;
+        doc.upgrade()
+    }
+
+    pub fn to_string(&self) -> Result<String, GameError> {
+        Ok(ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)
+    }
+
+    pub fn read(path: &Path) -> Result<SaveDocument, GameError> {
+        SaveDocument::from_str(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), GameError> {
+        std::fs::write(path, self.to_string()?)?;
+        Ok(())
+    }
+
+    /// each older version is changed into the one after it until it is current
+    fn upgrade(self) -> Result<SaveDocument, GameError> {
+        match self.version {
+            SAVE_VERSION => Ok(self),
+            // version 0 was the separate files, they are migrated by `legacy` not here
+            version => Err(GameError::SaveVersion(version)),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! the separate save files from before `SaveDocument`, only read to migrate them
+
+use std::{collections::HashSet, str::Chars};
+
+use crate::{prelude::*, story::StoryFlags};
+use super::{SaveDir, SaveDocument};
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use super::extract_item;
+
+    #[test]
+    fn saved_lines() {
+        let (name, at) = extract_item("Item_Space:[1.5, -20, 3.25]".chars()).unwrap();
+        assert_eq!(name, "Item Space");
+        assert_eq!(at, Vec3::new(1.5, -20., 3.25));
+        // from before the draw order was saved
+        let (_, at) = extract_item("Void:[4, 5]".chars()).unwrap();
+        assert_eq!(at, Vec3::new(4., 5., 0.));
+        assert!(extract_item("Void:[4]".chars()).is_err());
+    }
+}
+
+/// `name:[x, y, z]` for everything on the board
+const BOARD_FILE: &'static str = "game.sav";
+const OLD_BOARD_FILE: &'static str = "game.old";
+/// names between `[` and `]`, one a line
+const FOUND_FILE: &'static str = "found.sav";
+/// a ron `(ItemID, ItemID)` a line
+const MADE_FILE: &'static str = "made.sav";
+/// ron `StoryFlags`
+const STORY_FILE: &'static str = "story.sav";
+
+const FILES: [&str; 5] = [BOARD_FILE, OLD_BOARD_FILE, FOUND_FILE, MADE_FILE, STORY_FILE];
+
+/// a document from whichever old files are in `dir`, `None` if there are none
+pub fn read(dir: &SaveDir, items: &Items) -> Option<SaveDocument> {
+    use std::fs;
+    if !FILES.iter().any(|file| dir.file(file).exists()) {return None;}
+    let mut doc = SaveDocument::new();
+    let board = fs::read_to_string(dir.file(BOARD_FILE)).or_else(|_| fs::read_to_string(dir.file(OLD_BOARD_FILE)));
+    if let Ok(data) = board {
+        for line in data.lines() {
+            if line.len() < 6 {continue;}
+            match extract_item(line.chars()) {
+                Ok(item) => doc.board.push(item),
+                Err(e) => warn!("skipping saved item {:?}: {}", line, e),
+            }
+        }
+    }
+    if let Ok(data) = fs::read_to_string(dir.file(FOUND_FILE)) {
+        for line in data.lines() {
+            let line = line.trim().trim_start_matches('[').trim_end_matches(']').trim();
+            if line.is_empty() {continue;}
+            doc.found.push(line.replace('_', " "));
+        }
+    }
+    if let Ok(data) = fs::read_to_string(dir.file(MADE_FILE)) {
+        // only ids were kept so pairs with items that are gone can't be named
+        let mut made = HashSet::new();
+        for line in data.lines() {
+            let (item1, item2): (ItemID, ItemID) = if let Ok(i) = ron::from_str(line) {i} else {continue;};
+            let (item1, item2) = (items.resolve(item1), items.resolve(item2));
+            if !items.contains(&item1) || !items.contains(&item2) {continue;}
+            if made.insert(item1.first(item2)) {
+                doc.made.push((items.get(&item1).name().to_string(), items.get(&item2).name().to_string()));
+            }
+        }
+    }
+    if let Ok(data) = fs::read_to_string(dir.file(STORY_FILE)) {
+        match ron::from_str::<StoryFlags>(&data) {
+            Ok(story) => doc.story = story,
+            Err(e) => warn!("skipping {}: {}", STORY_FILE, e),
+        }
+    }
+    Some(doc)
+}
+
+/// renames the old files once the document has them so they are only ever migrated once
+pub fn retire(dir: &SaveDir) {
+    for file in FILES {
+        let path = dir.file(file);
+        if !path.exists() {continue;}
+        if let Err(e) = std::fs::rename(&path, dir.file(&format!("{}.migrated", file))) {
+            error!("{}", e);
+        }
+    }
+}
+
+/// `name:[x, y, z]`, saves from before z was kept have no z
+fn extract_item(mut chars: Chars) -> Result<(String, Vec3), GameError> {
+    let mut name = String::new();
+    while let Some(c) = chars.next() {
+        if c == ':' {break;}
+        if c == '_' {name.push(' '); continue;}
+        if c.is_whitespace() {continue;}
+        name.push(c);
+    };
+    let mut open = false;
+    let mut parts = vec![String::new()];
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {continue;}
+        if c == '[' {open = true; continue;}
+        if c == ']' {break;}
+        if !open {return Err(GameError::WrongChar(c, '['));}
+        if c == ',' {parts.push(String::new()); continue;}
+        if let Some(part) = parts.last_mut() {part.push(c);}
+    }
+    let x = parts[0].parse()?;
+    let y = parts.get(1).map_or("", |y| y.as_str()).parse()?;
+    let z = if let Some(z) = parts.get(2) {z.parse()?} else {0.};
+    Ok((name, Vec3::new(x, y, z)))
+}
@@ -32,7 +32,8 @@ fn test_sound(
     for event in events.iter() {
         match event {
             ItemEvent::Spawn(id) |
-            ItemEvent::SpawnAt(id,_) => {
+            ItemEvent::SpawnAt(id,_) |
+            ItemEvent::PlayerSpawnAt(id, _) => {
                 audio.play(items.get(id).sound());
             },
             _ => {},
@@ -1,8 +1,6 @@
 use bevy::ecs::schedule::ShouldRun;
 
-use crate::{prelude::*, ui::UiIcons};
-
-const SAVE_PATH: &'static str = "./assets/story.sav";
+use crate::{prelude::*, ui::{UiIcons, Pinned}, view::BoardView, serde::SaveGame, item::{config::ItemConfig, placement, layout::playable_area}};
 
 enum StoryEvent {
     SpawnItemSpace,
@@ -10,7 +8,8 @@ enum StoryEvent {
 
 pub struct BevyCount(pub usize);
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+/// how far the story has got, kept in the save
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
 pub struct StoryFlags {
     #[serde(default)]
     unlocked_app: bool,
@@ -20,32 +19,11 @@ pub struct StoryFlags {
     has_items_space: bool,
 }
 
-fn save_flag(flags: &StoryFlags) {
-    match std::fs::OpenOptions::new().create(true).write(true).truncate(true).open(SAVE_PATH) {
-    Ok(mut f) => {
-        if let Err(e) = ron::ser::to_writer_pretty(&mut f, flags, ron::ser::PrettyConfig::default()) {
-            error!("{}",e);
-        }
-    },
-    Err(e) => {error!("{}", e);}
-    }
-}
-
-impl Default for StoryFlags {
-    fn default() -> Self {
-        if let Ok(str) =  std::fs::read_to_string(SAVE_PATH) {
-            if let Ok(save) = ron::from_str(&str) {
-                return save;
-            }
-        }
-        StoryFlags { unlocked_app: false, has_trash: false, has_items_space: false }
-    }
-}
-
 pub struct StoryPlugin;
 impl Plugin for StoryPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<StoryFlags>();
+        app.init_resource::<UiConfig>();
         app.insert_resource(BevyCount(0));
         app.add_system_to_stage(CoreStage::First,count_bevys);
         app.add_system_set(
@@ -69,9 +47,14 @@ fn load_story(
     res: Res<StoryFlags>,
     mut events: EventWriter<ItemEvent>,
     mut story_events: EventWriter<StoryEvent>,
+    view: Res<BoardView>,
+    config: Res<ItemConfig>,
 ) {
     if res.has_trash {
-        events.send(ItemEvent::SpawnAt(ItemID::new("Trash"), Vec3::new(-590., 310., 0.0)));
+        // the trash lives in the top left corner
+        let area = playable_area(&view, config.board_size);
+        let at = placement::top_left(area, config.frame_size);
+        events.send(ItemEvent::SpawnAt(ItemID::new("Trash"), at.extend(0.0)));
     }
     if res.unlocked_app {
         events.send(ItemEvent::Spawn(ItemID::new("Totally a game")));
@@ -86,11 +69,13 @@ fn story_events(
     ui: Res<UiConfig>,
     icons: Res<UiIcons>,
     items: Res<Items>,
+    registry: Res<TagRegistry>,
+    pinned: Res<Pinned>,
     mut commands: Commands,
 ) {
     for event in events.iter() {
         match event {
-            StoryEvent::SpawnItemSpace => {crate::ui::spawn_item_space(&mut commands, &items, &icons, &ui);}
+            StoryEvent::SpawnItemSpace => {crate::ui::spawn_item_space(&mut commands, &items, &registry, &icons, &ui, &pinned);}
         }
     }
 }
@@ -102,7 +87,9 @@ fn count_bevys(
     for event in events.iter() {
         match event {
             ItemEvent::Spawn(id) |
-            ItemEvent::SpawnAt(id,_) => {
+            ItemEvent::SpawnAt(id,_) |
+            ItemEvent::PlayerSpawnAt(id, _) |
+            ItemEvent::Restore(id, ..) => {
                 if id == &ItemID::from("Bevy") {
                     res.0 += 1;
                 }
@@ -116,6 +103,7 @@ fn update_flags(
     mut res: ResMut<StoryFlags>,
     mut events: EventReader<ItemEvent>,
     mut story_events: EventWriter<StoryEvent>,
+    mut saves: EventWriter<SaveGame>,
 ) {
     let app_id = ItemID::new("Totally a game");
     let trash = ItemID::new("Trash");
@@ -124,7 +112,8 @@ fn update_flags(
     for event in events.iter() {
         match event {
             ItemEvent::Spawn(id) |
-            ItemEvent::SpawnAt(id, _) => {
+            ItemEvent::SpawnAt(id, _) |
+            ItemEvent::PlayerSpawnAt(id, _) => {
                 if !res.unlocked_app && id.id() == app_id.id() {
                     res.unlocked_app = true;
                     save = true;
@@ -143,7 +132,7 @@ fn update_flags(
         }
     }
     if save {
-        save_flag(&res);
+        saves.send(SaveGame);
     }
 }
 
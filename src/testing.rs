@@ -0,0 +1,146 @@
+//! the game without a window so whole player actions can be tested, used by the tests in `tests/`
+
+use std::path::PathBuf;
+
+use bevy::asset::AssetPlugin;
+
+use crate::{prelude::*, AssetDir, item::{ItemPlugin, pickup::Grabbed, physics::Seleced}, recipies::RecipiePlugin, script::ScriptPlugin, serde::{SaveDir, SaveLoadPlugin, SaveGame}, story::StoryPlugin, ui::UiIconsPlugin, view::HeadlessViewPlugin};
+
+/// frames a drop takes to turn into the items it makes
+const SETTLE_FRAMES: usize = 1;
+
+/// a headless app with the item, recipie, story, save and script plugins, saving into its own folder
+pub struct Harness {
+    pub app: App,
+    dir: PathBuf,
+    mods: PathBuf,
+    /// the folder is only removed by the harness that made it
+    owner: bool,
+}
+
+impl Harness {
+    /// `name` keeps the saves of each test apart, items, recipies and scripts are read from `mods`
+    pub fn new(name: &str, mods: &str) -> Harness {
+        Harness::with_saves(name, mods, &[])
+    }
+
+    /// starts from `(file, contents)` already in the save folder
+    pub fn with_saves(name: &str, mods: &str, files: &[(&str, &str)]) -> Harness {
+        let dir = std::env::temp_dir().join(format!("void-a-nomicon-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("can make a save folder");
+        for (file, contents) in files {
+            std::fs::write(dir.join(file), contents).expect("can write a save");
+        }
+        let mods = PathBuf::from(mods);
+        Harness { app: Harness::app(&dir, &mods), dir, mods, owner: true }
+    }
+
+    /// `file` in the save folder
+    pub fn file(&self, file: &str) -> PathBuf {
+        self.dir.join(file)
+    }
+
+    pub fn has_file(&self, file: &str) -> bool {
+        self.file(file).exists()
+    }
+
+    /// a new game with the same mods reading the saves of `other`
+    pub fn reopen(other: &Harness) -> Harness {
+        Harness { app: Harness::app(&other.dir, &other.mods), dir: other.dir.clone(), mods: other.mods.clone(), owner: false }
+    }
+
+    fn app(dir: &PathBuf, mods: &PathBuf) -> App {
+        let mut app = App::new();
+        app.insert_resource(SaveDir(dir.clone()))
+            .insert_resource(AssetDir(mods.clone()))
+            .add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin)
+            .add_plugin(HeadlessViewPlugin)
+            .add_plugin(UiIconsPlugin)
+            .add_plugin(ItemPlugin)
+            .add_plugin(RecipiePlugin)
+            .add_plugin(SaveLoadPlugin)
+            .add_plugin(StoryPlugin)
+            .add_plugin(ScriptPlugin);
+        // runs the startup systems
+        app.update();
+        app
+    }
+
+    pub fn send(&mut self, event: ItemEvent) {
+        self.app.world.resource_mut::<Events<ItemEvent>>().send(event);
+    }
+
+    pub fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    /// every item with `id` on the board
+    pub fn entities(&mut self, id: ItemID) -> Vec<Entity> {
+        let mut query = self.app.world.query_filtered::<(Entity, &ItemID), With<Size>>();
+        query.iter(&self.app.world).filter(|(_, other)| **other == id).map(|(e, _)| e).collect()
+    }
+
+    /// names of everything on the board in alphabetical order
+    pub fn board(&mut self) -> Vec<String> {
+        let mut query = self.app.world.query_filtered::<&ItemID, With<Size>>();
+        let items = self.app.world.resource::<Items>();
+        let mut names: Vec<String> = query.iter(&self.app.world).map(|id| items.get(id).name().to_string()).collect();
+        names.sort();
+        names
+    }
+
+    /// names and spots of everything on the board from the bottom of the draw order to the top
+    pub fn stack(&mut self) -> Vec<(String, Vec2)> {
+        let mut query = self.app.world.query_filtered::<(&ItemID, &Transform), With<Size>>();
+        let items = self.app.world.resource::<Items>();
+        let mut stack: Vec<(&ItemID, &Transform)> = query.iter(&self.app.world).collect();
+        stack.sort_by(|(_, a), (_, b)| a.translation.z.total_cmp(&b.translation.z));
+        stack.into_iter().map(|(id, t)| (items.get(id).name().to_string(), t.translation.truncate())).collect()
+    }
+
+    pub fn assert_board(&mut self, expected: &[&str]) {
+        let mut expected: Vec<String> = expected.iter().map(|name| name.to_string()).collect();
+        expected.sort();
+        assert_eq!(self.board(), expected);
+    }
+
+    /// spawns `id` as close to `at` as there is room and gives back its entity
+    pub fn spawn(&mut self, id: ItemID, at: Vec2) -> Entity {
+        let before = self.entities(id);
+        self.send(ItemEvent::SpawnAt(id, at.extend(0.)));
+        self.step(1);
+        self.entities(id).into_iter().find(|e| !before.contains(e)).expect("item was spawned")
+    }
+
+    /// carries `item` onto `target` and lets go the way the player would
+    pub fn drop_on(&mut self, item: Entity, target: Entity) {
+        let at = self.app.world.get::<Transform>(target).expect("target is on the board").translation;
+        self.app.world.entity_mut(item).insert(Grabbed(Vec2::ZERO));
+        if let Some(mut t) = self.app.world.get_mut::<Transform>(item) {
+            t.translation.x = at.x;
+            t.translation.y = at.y;
+        }
+        self.app.world.insert_resource(Seleced(Some(item)));
+        self.step(1);
+        self.send(ItemEvent::Drop);
+        self.step(SETTLE_FRAMES);
+    }
+
+    /// writes the board to the save folder now
+    pub fn save(&mut self) {
+        self.app.world.resource_mut::<Events<SaveGame>>().send(SaveGame);
+        self.step(1);
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        if self.owner {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+}
@@ -2,26 +2,57 @@ use std::collections::HashMap;
 
 use bevy::input::mouse::MouseWheel;
 
-use crate::prelude::*;
+use crate::{prelude::*, item::{config::ItemConfig, history::History, placement::random_spot}};
 
 pub mod ui_config;
 mod tooltip;
+mod context_menu;
+
+pub use context_menu::lock;
+
+/// height of the tool tip bar along the bottom of the window
+pub const TOOLTIP_HEIGHT: f32 = 150.;
+/// size of the item space panel in the top right of the window
+pub const ITEM_SPACE_SIZE: Vec2 = Vec2::new(220., 570.);
+
+/// `screen` is a window position with the origin in the bottom left
+pub fn over_item_space(screen: Vec2, window_size: Vec2) -> bool {
+    screen.x > window_size.x - ITEM_SPACE_SIZE.x && screen.y > window_size.y - ITEM_SPACE_SIZE.y
+}
+
+/// the pointer is on a menu or button so the board underneath should ignore it
+pub fn pointer_on_ui(interactions: &Query<&Interaction>) -> bool {
+    interactions.iter().any(|i| *i != Interaction::None)
+}
+
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<TTTextStyle>();
-        app.init_resource::<UiIcons>();
+        app.add_plugin(UiIconsPlugin);
         app.init_resource::<ui_config::UiConfig>();
         app.add_plugin(tooltip::ToolTipPlugin);
-        app.add_startup_system(load_ui_icons);
         app.add_startup_system(spawn_tool_tip);
         app.add_system(spawn_item_space_items);
         app.add_system(click_item);
         app.add_system(move_window);
+        app.init_resource::<Pinned>();
+        app.init_resource::<context_menu::ContextMenu>();
+        app.add_system(context_menu::open_menu);
+        app.add_system(context_menu::menu_click.after(context_menu::open_menu));
     }
 }
 
+/// the icons items and the ui are drawn with, the only part of the ui items need without a window
+pub struct UiIconsPlugin;
+
+impl Plugin for UiIconsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UiIcons>();
+        app.add_startup_system(load_ui_icons);
+    }
+}
 
 #[derive(Default)]
 pub struct UiIcons{ 
@@ -98,7 +129,7 @@ fn spawn_tool_tip(
     use bevy::prelude::Size;
     commands.spawn_bundle(NodeBundle{
         style: Style {
-            size: Size { width: Val::Percent(100.), height: Val::Px(150.) },
+            size: Size { width: Val::Percent(100.), height: Val::Px(TOOLTIP_HEIGHT) },
             flex_wrap: FlexWrap::Wrap,
             ..Default::default()
         },
@@ -211,13 +242,15 @@ fn spawn_tool_tip(
 pub fn spawn_item_space(
     commands: &mut Commands,
     items: &Items,
+    registry: &TagRegistry,
     icons: &UiIcons,
     ui: &UiConfig,
+    pinned: &Pinned,
 ) {
     use bevy::prelude::Size;
     let c = commands.spawn_bundle(NodeBundle{
         style: Style {
-            size: Size { width: Val::Px(220.), height: Val::Px(570.) },
+            size: Size { width: Val::Px(ITEM_SPACE_SIZE.x), height: Val::Px(ITEM_SPACE_SIZE.y) },
             flex_wrap: FlexWrap::Wrap,
             position_type: PositionType::Absolute,
             position: UiRect{top: Val::Px(0.), right: Val::Px(0.), left: Val::Auto, bottom: Val::Auto},
@@ -306,35 +339,45 @@ pub fn spawn_item_space(
         });
         c
     });
-    for item in items.found() {
-        spawn_item_space_item(items, item, commands, c, ui, icons);
+    // pinned items stay in front where they were put
+    let found = items.found();
+    let front = pinned.0.iter().filter(|id| found.contains(id));
+    let rest = found.iter().filter(|id| !pinned.0.contains(id));
+    for item in front.chain(rest) {
+        spawn_item_space_item(items, registry, *item, commands, c, ui, icons);
     }
 }
 
 #[derive(Component)]
-struct ItemSpace;
+pub(crate) struct ItemSpace;
+
+/// what was pinned to the front of the item space, last pinned first, kept in the save
+#[derive(Debug, Default, Clone)]
+pub struct Pinned(pub Vec<ItemID>);
 
 #[derive(Component)]
 pub struct ItemSpaceItem;
 
 fn spawn_item_space_items(
     mut commands: Commands,
-    mut events: EventReader<ItemEvent>,
+    mut notices: EventReader<ItemNotice>,
     items: Res<Items>,
+    registry: Res<TagRegistry>,
     query: Query<Entity, With<ItemSpace>>,
     icons: Res<UiIcons>,
     ui: Res<UiConfig>,
 ) {
     let item_space = if let Ok(e) = query.get_single() {e} else {return;};
-    for event in events.iter() {
-        if let ItemEvent::Found(id) = event {
-            spawn_item_space_item(&items, *id, &mut commands, item_space, &ui, &icons);
+    for notice in notices.iter() {
+        if let ItemNotice::Found(id) = notice {
+            spawn_item_space_item(&items, &registry, *id, &mut commands, item_space, &ui, &icons);
         }
     }
 }
 
 fn spawn_item_space_item(
     items: &Items,
+    registry: &TagRegistry,
     id: ItemID,
     commands: &mut Commands,
     item_space: Entity,
@@ -343,10 +386,13 @@ fn spawn_item_space_item(
 ) {
     let item = items.get(&id);
     if item.name() == "Debug Item" {return;}
+    let tag_style = items.tags(&id).map(|tags| registry.style(tags)).unwrap_or_default();
     commands.entity(item_space).with_children(|p| {
         p.spawn_bundle(ButtonBundle{
             image: icons.get("item_frame").into(),
             style: ui.frame_style.clone(),
+            // ui has nothing to draw behind the frame so glow falls back to a tint
+            color: tag_style.tint.or(tag_style.glow).unwrap_or(Color::WHITE).into(),
             ..Default::default()
         })
         .insert(ItemSpaceItem)
@@ -358,18 +404,36 @@ fn spawn_item_space_item(
                 focus_policy: bevy::ui::FocusPolicy::Pass,
                 ..Default::default()
             });
+            if let Some(badge) = tag_style.badge {
+                p.spawn_bundle(ImageBundle{
+                    image: badge.into(),
+                    style: ui.badge_style.clone(),
+                    focus_policy: bevy::ui::FocusPolicy::Pass,
+                    ..Default::default()
+                });
+            }
         });
     });
 }
 
+/// the player taking `id` out of the item space onto the board, undone like anything else they do
+pub fn take_out(id: ItemID, history: &mut History, view: &crate::view::BoardView, config: &ItemConfig) -> ItemEvent {
+    history.begin();
+    let at = random_spot(view, config.board_size, config.frame_size);
+    ItemEvent::PlayerSpawnAt(id, at.extend(0.0))
+}
+
 fn click_item(
     query: Query<(&ItemID, &Interaction), (With<ItemSpaceItem>, Changed<Interaction>)>,
     mut events: EventWriter<ItemEvent>,
+    mut history: ResMut<History>,
+    view: Res<crate::view::BoardView>,
+    config: Res<ItemConfig>,
 ) {
     for (id, click) in query.iter() {
         match click {
             Interaction::Clicked => {
-            events.send(ItemEvent::Spawn(*id));
+                events.send(take_out(*id, &mut history, &view, &config));
             }
             Interaction::Hovered => {
                 events.send(ItemEvent::ToolTip(*id));
@@ -382,12 +446,16 @@ fn click_item(
 fn move_window(
     mut events: EventReader<MouseWheel>,
     mut query: Query<&mut Style, With<ItemSpace>>,
+    cursor: Res<WorldCursor>,
+    view: Res<crate::view::BoardView>,
 ) {
     let mut style = if let Ok(e) = query.get_single_mut() {e} else {return;};
     let mut delta = 0.0;
     for e in events.iter() {
         delta += e.y;
     }
+    // anywhere else the wheel zooms the board
+    if !cursor.screen.map_or(false, |screen| over_item_space(screen, view.size)) {return;}
     if delta != 0. {
         delta *= 10.;
         if let Val::Px(v) = style.position.bottom {style.position.bottom = Val::Px(v + delta);
@@ -0,0 +1,228 @@
+use crate::{prelude::*, view::BoardView, recipies::Recipies, item::{spatial::SpatialIndex, physics::Locked, history::History, layers::part}};
+
+use super::{ItemSpace, ItemSpaceItem, Pinned, TTTextStyle, pointer_on_ui};
+
+/// the open menu and the board item it is for
+#[derive(Debug, Default)]
+pub struct ContextMenu {
+    menu: Option<Entity>,
+    item: Option<Entity>,
+}
+
+/// on the corner mark of a locked item
+#[derive(Component)]
+pub struct LockBadge;
+
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+    Duplicate,
+    Trash,
+    Lock,
+    Unlock,
+    Pin,
+    Recipes,
+    Info,
+}
+
+impl MenuAction {
+    fn label(&self) -> &'static str {
+        match self {
+            MenuAction::Duplicate => "Duplicate",
+            MenuAction::Trash => "Send to trash",
+            MenuAction::Lock => "Lock position",
+            MenuAction::Unlock => "Unlock position",
+            MenuAction::Pin => "Pin to item space",
+            MenuAction::Recipes => "Show recipes",
+            MenuAction::Info => "Show info",
+        }
+    }
+}
+
+/// keeps `e` where it is and marks its corner, `size` is the item's
+pub fn lock(commands: &mut Commands, e: Entity, size: Vec2) {
+    let badge = commands.spawn_bundle(SpriteBundle {
+        sprite: Sprite { color: Color::rgba(0.2, 0.2, 0.2, 0.8), custom_size: Some(size * 0.2), ..Default::default() },
+        transform: Transform::from_translation((size * Vec2::new(-0.4, -0.4)).extend(part::BADGE)),
+        ..Default::default()
+    }).insert(LockBadge).id();
+    commands.entity(e).insert(Locked).add_child(badge);
+}
+
+fn close(commands: &mut Commands, menu: &mut ContextMenu) {
+    if let Some(e) = menu.menu.take() {
+        commands.entity(e).despawn_recursive();
+    }
+    menu.item = None;
+}
+
+/// right clicking a board item opens its menu, clicking anywhere else or escape closes it
+pub fn open_menu(
+    mut commands: Commands,
+    mut menu: ResMut<ContextMenu>,
+    (mouse, keys): (Res<Input<MouseButton>>, Res<Input<KeyCode>>),
+    cursor: Res<WorldCursor>,
+    view: Res<BoardView>,
+    index: Res<SpatialIndex>,
+    board: Query<(&ItemID, Option<&Locked>), With<Size>>,
+    item_space: Query<(), With<ItemSpace>>,
+    ui: Query<&Interaction>,
+    items: Res<Items>,
+    style: Res<TTTextStyle>,
+) {
+    let right = mouse.just_pressed(MouseButton::Right);
+    if menu.menu.is_some() && (right || keys.just_pressed(KeyCode::Escape) || (cursor.just_pressed && !pointer_on_ui(&ui))) {
+        close(&mut commands, &mut menu);
+    }
+    if !right || pointer_on_ui(&ui) {return;}
+    let (screen, world) = if let (Some(s), Some(w)) = (cursor.screen, cursor.world) {(s, w)} else {return;};
+    let item = if let Some(e) = index.top_at_point(world) {e} else {return;};
+    let (id, locked) = if let Ok(i) = board.get(item) {i} else {return;};
+    let trash = ItemID::new("Trash");
+    let mut actions = Vec::new();
+    if *id != trash {
+        if items.found().contains(id) {actions.push(MenuAction::Duplicate);}
+        if board.iter().any(|(other, _)| *other == trash) {actions.push(MenuAction::Trash);}
+    }
+    actions.push(if locked.is_some() {MenuAction::Unlock} else {MenuAction::Lock});
+    if !item_space.is_empty() && items.found().contains(id) {actions.push(MenuAction::Pin);}
+    actions.push(MenuAction::Recipes);
+    actions.push(MenuAction::Info);
+    let root = commands.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect { left: Val::Px(screen.x), right: Val::Auto, top: Val::Px(view.size.y - screen.y), bottom: Val::Auto },
+            flex_direction: FlexDirection::ColumnReverse,
+            padding: UiRect::all(Val::Px(5.)),
+            ..Default::default()
+        },
+        color: Color::BEIGE.into(),
+        ..Default::default()
+    })
+    // blocks clicks on the menu from reaching the board
+    .insert(Interaction::default())
+    .insert(Name::new("Context Menu"))
+    .with_children(|p| {
+        for action in actions {
+            p.spawn_bundle(ButtonBundle {
+                style: Style {
+                    padding: UiRect::all(Val::Px(4.)),
+                    ..Default::default()
+                },
+                color: Color::rgb(0.85, 0.8, 0.7).into(),
+                ..Default::default()
+            })
+            .insert(action)
+            .with_children(|p| {
+                p.spawn_bundle(TextBundle {
+                    text: Text { sections: vec![TextSection { style: style.description.clone(), value: action.label().to_string() }], alignment: TextAlignment::default() },
+                    focus_policy: bevy::ui::FocusPolicy::Pass,
+                    ..Default::default()
+                });
+            });
+        }
+    })
+    .id();
+    menu.menu = Some(root);
+    menu.item = Some(item);
+}
+
+pub fn menu_click(
+    mut commands: Commands,
+    mut menu: ResMut<ContextMenu>,
+    clicked: Query<(&Interaction, &MenuAction), Changed<Interaction>>,
+    board: Query<(Entity, &ItemID, &Transform), With<Size>>,
+    slots: Query<(Entity, &ItemID), With<ItemSpaceItem>>,
+    item_space: Query<Entity, With<ItemSpace>>,
+    (items, recipies, registry): (Res<Items>, Res<Recipies>, Res<TagRegistry>),
+    mut history: ResMut<History>,
+    mut events: EventWriter<ItemEvent>,
+    style: Res<TTTextStyle>,
+    styles: Query<&Style>,
+    (sizes, badges): (Query<&Size>, Query<(Entity, &Parent), With<LockBadge>>),
+    mut pinned: ResMut<Pinned>,
+) {
+    let action = if let Some((_, a)) = clicked.iter().find(|(i, _)| **i == Interaction::Clicked) {*a} else {return;};
+    let root = if let Some(e) = menu.menu {e} else {return;};
+    let item = menu.item;
+    close(&mut commands, &mut menu);
+    let (e, id, t) = if let Some(i) = item.and_then(|e| board.get(e).ok()) {i} else {return;};
+    let mut lines = Vec::new();
+    match action {
+        MenuAction::Duplicate => {
+            history.begin();
+            events.send(ItemEvent::PlayerSpawnAt(*id, t.translation.truncate().extend(0.0)));
+        },
+        MenuAction::Trash => {
+            let trash = ItemID::new("Trash");
+            if let Some((trash, _, _)) = board.iter().find(|(_, other, _)| **other == trash) {
+                // the same as dropping it in so it can be undone
+                history.begin();
+                events.send(ItemEvent::CheckCombine(e, trash));
+            }
+        },
+        MenuAction::Lock => {
+            let size = if let Ok(s) = sizes.get(e) {s.0} else {return;};
+            lock(&mut commands, e, size);
+        },
+        MenuAction::Unlock => {
+            commands.entity(e).remove::<Locked>();
+            for (badge, parent) in badges.iter() {
+                if **parent == e {commands.entity(badge).despawn_recursive();}
+            }
+        },
+        MenuAction::Pin => {
+            let slot = slots.iter().find(|(_, other)| *other == id);
+            if let (Some((slot, _)), Ok(space)) = (slot, item_space.get_single()) {
+                commands.entity(space).insert_children(0, &[slot]);
+                pinned.0.retain(|other| other != id);
+                pinned.0.insert(0, *id);
+            }
+        },
+        MenuAction::Recipes => {
+            let name = |id: &ItemID| items.get(id).name().to_string();
+            for ((item1, item2), makes) in recipies.made_with(*id) {
+                let makes: Vec<String> = makes.iter().map(name).collect();
+                lines.push(format!("{} + {} = {}", name(&item1), name(&item2), makes.join(", ")));
+            }
+            lines.sort();
+            if lines.is_empty() {lines.push("Nothing made with this yet".to_string());}
+        },
+        MenuAction::Info => {
+            let item = items.get(id);
+            lines.push(item.name().to_string());
+            lines.push(item.description().to_string());
+            if let Some(tags) = items.tags(id) {
+                let mut names: Vec<String> = tags.iter().map(|tag| registry.display_name(tag)).collect();
+                names.sort();
+                if !names.is_empty() {lines.push(format!("Tags: {}", names.join(", ")));}
+            }
+            events.send(ItemEvent::ToolTip(*id));
+        },
+    }
+    if lines.is_empty() {return;}
+    // the answer shows up where the menu was and closes like it
+    let position = if let Ok(s) = styles.get(root) {s.position} else {return;};
+    let panel = commands.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position,
+            flex_direction: FlexDirection::ColumnReverse,
+            padding: UiRect::all(Val::Px(5.)),
+            ..Default::default()
+        },
+        color: Color::BEIGE.into(),
+        ..Default::default()
+    })
+    .insert(Interaction::default())
+    .insert(Name::new("Context Menu"))
+    .with_children(|p| {
+        for line in lines {
+            p.spawn_bundle(TextBundle {
+                text: Text { sections: vec![TextSection { style: style.description.clone(), value: line }], alignment: TextAlignment::default() },
+                ..Default::default()
+            });
+        }
+    })
+    .id();
+    menu.menu = Some(panel);
+}
@@ -1,9 +1,10 @@
-use crate::{prelude::*, item::Items};
+use crate::{prelude::*, item::{Items, spatial::SpatialIndex}};
 
 pub struct ToolTipPlugin;
 impl Plugin for ToolTipPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(set_tooltip);
+        app.add_system(long_press_tooltip);
         app.add_system(update_tooltip);
     }
 }
@@ -37,35 +38,18 @@ fn update_tooltip(
 }
 
 pub fn set_tooltip(
-    query: Query<(&Transform, &Size, &ItemID)>,
-    windows: Res<Windows>,
-    camera: Query<(&Camera, &GlobalTransform), With<crate::MainCam>>,
+    query: Query<&ItemID>,
+    index: Res<SpatialIndex>,
+    cursor: Res<WorldCursor>,
     input: Res<Input<MouseButton>>,
     mut events: EventWriter<ItemEvent>
 ) {
-    let (camera, global_transform) = camera.single();
-    let window = windows.get_primary().unwrap();
-    if let Some(screen_pos) = window.cursor_position() {
-        let window_size = Vec2::new(window.width() as f32, window.height() as f32);
-
-        let ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
-
-        let ndc_to_world = global_transform.compute_matrix() * camera.projection_matrix().inverse();
-
-        let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
-
-        let world_pos: Vec2 = world_pos.truncate();
-
+    // a finger only shows tool tips on a long press
+    if cursor.touch {return;}
+    if let Some(world_pos) = cursor.world {
         //if input.just_pressed(MouseButton::Right) {
-            let mut hits: Vec<(ItemID, f32)> = Vec::new();
-            for (transform, size, item) in query.iter() {
-                if crate::item::physics::box_point_hit(size.0, transform.translation.truncate(), world_pos) {
-                    hits.push((*item, transform.translation.z));
-                }
-            }
-            if hits.len() != 0 {
-                hits.sort_by(|(_, a),(_, b)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Greater));
-                events.send(ItemEvent::ToolTip(hits[0].0));
+            if let Some(item) = index.top_at_point(world_pos).and_then(|e| query.get(e).ok()) {
+                events.send(ItemEvent::ToolTip(*item));
             }
         //}
         // } else if input.just_pressed(MouseButton::Right) {
@@ -81,4 +65,36 @@ pub fn set_tooltip(
         //     }
         // }
     }
-}
\ No newline at end of file
+}
+/// how long a finger has to be held still in seconds
+const LONG_PRESS: f64 = 0.5;
+/// how far a finger can wander and still be held still
+const LONG_PRESS_SLOP: f32 = 10.;
+
+/// fingers can't hover so holding one still on an item shows its tool tip
+fn long_press_tooltip(
+    query: Query<&ItemID>,
+    index: Res<SpatialIndex>,
+    cursor: Res<WorldCursor>,
+    time: Res<Time>,
+    mut held: Local<Option<(f64, Vec2)>>,
+    mut events: EventWriter<ItemEvent>,
+) {
+    let (screen, world) = match (cursor.touch && cursor.pressed, cursor.screen, cursor.world) {
+        (true, Some(s), Some(w)) => (s, w),
+        _ => {*held = None; return;},
+    };
+    if cursor.just_pressed {
+        *held = Some((time.seconds_since_startup(), screen));
+        return;
+    }
+    let (start, at) = if let Some(h) = *held {h} else {return;};
+    if screen.distance(at) > LONG_PRESS_SLOP {
+        *held = None;
+    } else if time.seconds_since_startup() - start >= LONG_PRESS {
+        if let Some(item) = index.top_at_point(world).and_then(|e| query.get(e).ok()) {
+            events.send(ItemEvent::ToolTip(*item));
+        }
+        *held = None;
+    }
+}
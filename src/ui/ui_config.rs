@@ -3,6 +3,7 @@ use bevy::prelude::*;
 pub struct UiConfig {
     pub frame_style: Style,
     pub icon_style: Style,
+    pub badge_style: Style,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -30,6 +31,7 @@ impl Default for UiConfig {
                     margin: UiRect::all(Val::Auto),
                     ..Default::default()
                 },
+                badge_style: badge_style(f.frame_size),
             };
         };
         if let Ok(data) = ron::from_str::<UiConfigData>(&data) {
@@ -45,6 +47,7 @@ impl Default for UiConfig {
                     margin: UiRect::all(Val::Auto),
                     ..Default::default()
                 },
+                badge_style: badge_style(f.frame_size),
             }
         }
     }
@@ -60,6 +63,17 @@ impl From<UiConfigData> for UiConfig {
             margin: UiRect::all(Val::Auto),
             ..Default::default()
         },
+        badge_style: badge_style(f.frame_size),
         }
     }
 }
+
+/// sits in the top right corner of an item frame
+fn badge_style(frame_size: f32) -> Style {
+    Style {
+        size: Size { width: Val::Px(frame_size * 0.3), height: Val::Px(frame_size * 0.3) },
+        position_type: PositionType::Absolute,
+        position: UiRect { top: Val::Px(0.), right: Val::Px(0.), left: Val::Auto, bottom: Val::Auto },
+        ..Default::default()
+    }
+}
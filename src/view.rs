@@ -0,0 +1,191 @@
+use bevy::input::InputSystem;
+
+use crate::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use bevy::{prelude::*, input::{InputPlugin, touch::{TouchInput, TouchPhase}}};
+    use super::{BoardView, WorldCursor, touch_cursor};
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(InputPlugin)
+            .init_resource::<WorldCursor>()
+            .init_resource::<BoardView>()
+            .add_system_to_stage(CoreStage::PreUpdate, touch_cursor.after(bevy::input::InputSystem));
+        app
+    }
+
+    fn touch(app: &mut App, id: u64, phase: TouchPhase, position: Vec2) -> WorldCursor {
+        app.world.resource_mut::<Events<TouchInput>>().send(TouchInput { phase, position, force: None, id });
+        app.update();
+        *app.world.resource::<WorldCursor>()
+    }
+
+    #[test]
+    fn finger_drags_cursor() {
+        let mut app = app();
+        let cursor = touch(&mut app, 1, TouchPhase::Started, Vec2::new(640., 360.));
+        assert!(cursor.touch && cursor.pressed && cursor.just_pressed);
+        assert_eq!(cursor.world, Some(Vec2::ZERO));
+        let cursor = touch(&mut app, 1, TouchPhase::Moved, Vec2::new(740., 300.));
+        assert!(cursor.pressed && !cursor.just_pressed);
+        assert_eq!(cursor.world, Some(Vec2::new(100., -60.)));
+        let cursor = touch(&mut app, 1, TouchPhase::Ended, Vec2::new(740., 300.));
+        assert!(!cursor.pressed && cursor.just_released);
+        assert_eq!(cursor.world, Some(Vec2::new(100., -60.)));
+    }
+
+    #[test]
+    fn second_finger_lets_go() {
+        let mut app = app();
+        let cursor = touch(&mut app, 1, TouchPhase::Started, Vec2::new(100., 100.));
+        assert!(cursor.pressed);
+        let cursor = touch(&mut app, 2, TouchPhase::Started, Vec2::new(300., 100.));
+        assert!(!cursor.pressed && cursor.just_released);
+    }
+}
+
+pub struct ViewPlugin;
+
+impl Plugin for ViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(HeadlessViewPlugin);
+        app.add_system_to_stage(CoreStage::PreUpdate, update_view.after(InputSystem));
+        app.add_system_to_stage(CoreStage::PreUpdate, touch_cursor.after(update_view));
+    }
+}
+
+/// the view and cursor without a window or camera to fill them in, they keep their defaults
+pub struct HeadlessViewPlugin;
+
+impl Plugin for HeadlessViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldCursor>();
+        app.init_resource::<BoardView>();
+    }
+}
+
+/// where the mouse or finger is this frame, `None` when it is outside the window
+/// or no finger is down, `pressed` is the left mouse button or the first finger
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WorldCursor {
+    pub screen: Option<Vec2>,
+    pub world: Option<Vec2>,
+    pub pressed: bool,
+    pub just_pressed: bool,
+    pub just_released: bool,
+    /// the cursor is a finger so it can't hover
+    pub touch: bool,
+}
+
+/// window size and where the main camera is looking
+#[derive(Debug, Clone, Copy)]
+pub struct BoardView {
+    pub size: Vec2,
+    pub center: Vec2,
+    pub scale: f32,
+}
+
+impl Default for BoardView {
+    fn default() -> Self {
+        BoardView { size: Vec2::new(1280., 720.), center: Vec2::ZERO, scale: 1. }
+    }
+}
+
+impl BoardView {
+    pub fn to_world(&self, screen: Vec2) -> Vec2 {
+        self.center + (screen - self.size / 2.) * self.scale
+    }
+
+    pub fn to_screen(&self, world: Vec2) -> Vec2 {
+        (world - self.center) / self.scale + self.size / 2.
+    }
+}
+
+/// turns a window position (origin bottom left) into a world position for `camera`
+pub fn screen_to_world(
+    screen_pos: Vec2,
+    window_size: Vec2,
+    camera: &Camera,
+    global_transform: &GlobalTransform,
+) -> Vec2 {
+    let ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
+
+    let ndc_to_world = global_transform.compute_matrix() * camera.projection_matrix().inverse();
+
+    let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
+
+    world_pos.truncate()
+}
+
+pub fn update_view(
+    windows: Res<Windows>,
+    camera: Query<(&Camera, &GlobalTransform, &OrthographicProjection), With<MainCam>>,
+    mouse: Res<Input<MouseButton>>,
+    mut cursor: ResMut<WorldCursor>,
+    mut view: ResMut<BoardView>,
+) {
+    cursor.pressed = mouse.pressed(MouseButton::Left);
+    cursor.just_pressed = mouse.just_pressed(MouseButton::Left);
+    cursor.just_released = mouse.just_released(MouseButton::Left);
+    cursor.touch = false;
+    let window = if let Some(w) = windows.get_primary() {w} else {return;};
+    let (camera, global_transform, projection) = if let Ok(c) = camera.get_single() {c} else {return;};
+    let window_size = Vec2::new(window.width() as f32, window.height() as f32);
+    view.size = window_size;
+    view.center = global_transform.translation().truncate();
+    view.scale = projection.scale;
+    cursor.screen = window.cursor_position();
+    cursor.world = cursor.screen.map(|screen_pos| {
+        screen_to_world(screen_pos, window_size, camera, global_transform)
+    });
+}
+
+#[derive(Debug, Default)]
+pub struct TouchState {
+    /// the finger standing in for the mouse
+    finger: Option<u64>,
+    /// two fingers are down so nothing is dragged until they all lift
+    pinching: bool,
+}
+
+/// the first finger down stands in for the mouse, a second finger starts a pinch instead
+pub fn touch_cursor(
+    touches: Res<Touches>,
+    view: Res<BoardView>,
+    mut cursor: ResMut<WorldCursor>,
+    mut state: Local<TouchState>,
+) {
+    if state.pinching {
+        if touches.iter().next().is_none() {state.pinching = false;}
+        return;
+    }
+    if state.finger.is_none() {
+        state.finger = touches.iter_just_pressed().map(|t| t.id()).min();
+    }
+    let id = if let Some(id) = state.finger {id} else {return;};
+    let touch = touches.get_pressed(id)
+        .or_else(|| touches.get_released(id))
+        .or_else(|| touches.iter_just_cancelled().find(|t| t.id() == id));
+    let touch = if let Some(t) = touch {t} else {
+        state.finger = None;
+        return;
+    };
+    let mut pressed = touches.get_pressed(id).is_some();
+    if touches.iter().count() > 1 {
+        // let go of anything held so the pinch doesn't drag it
+        state.pinching = true;
+        pressed = false;
+    }
+    cursor.screen = Some(touch.position());
+    cursor.world = Some(view.to_world(touch.position()));
+    cursor.touch = true;
+    cursor.just_pressed = pressed && touches.just_pressed(id);
+    cursor.pressed = pressed;
+    cursor.just_released = !pressed;
+    if !pressed {
+        state.finger = None;
+    }
+}
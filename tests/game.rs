@@ -0,0 +1,215 @@
+//! whole player actions run through the headless game
+
+use void_a_nomicon::{prelude::*, item::{history::{History, Change}, layers::DrawOrder, physics::Locked}, recipies::Recipies, script::Scripts, testing::Harness, ui::Pinned};
+
+/// just the items and recipies the tests need, so changes to the shipped mods can't break them
+const MODS: &str = "tests/mods";
+
+#[test]
+fn void_on_void_makes_space() {
+    let mut game = Harness::new("void_on_void", MODS);
+    let void = ItemID::from("Void");
+    let a = game.spawn(void, Vec2::ZERO);
+    let b = game.spawn(void, Vec2::ZERO);
+    game.assert_board(&["Void", "Void"]);
+    game.drop_on(a, b);
+    game.assert_board(&["Space"]);
+    assert!(game.app.world.resource::<Items>().found().contains(&ItemID::from("Space")));
+}
+
+#[test]
+fn combine_resolves_in_one_frame() {
+    let mut game = Harness::new("one_frame", MODS);
+    let void = ItemID::from("Void");
+    let (a, b) = (game.spawn(void, Vec2::ZERO), game.spawn(void, Vec2::ZERO));
+    game.drop_on(a, b);
+    // dropped, checked, combined and spawned all in the frame after letting go
+    assert_eq!(game.entities(ItemID::from("Space")).len(), 1);
+    assert!(game.entities(void).is_empty());
+}
+
+#[test]
+fn made_where_it_was_combined() {
+    let mut game = Harness::new("made_in_place", MODS);
+    let void = ItemID::from("Void");
+    let (a, b) = (game.spawn(void, Vec2::ZERO), game.spawn(void, Vec2::ZERO));
+    let at = game.app.world.get::<Transform>(b).unwrap().translation.truncate();
+    game.drop_on(a, b);
+    let space = game.entities(ItemID::from("Space"))[0];
+    assert_eq!(game.app.world.get::<Transform>(space).unwrap().translation.truncate(), at);
+}
+
+#[test]
+fn made_twice_is_refused() {
+    let mut game = Harness::new("made_twice", MODS);
+    let void = ItemID::from("Void");
+    let (a, b) = (game.spawn(void, Vec2::ZERO), game.spawn(void, Vec2::ZERO));
+    game.drop_on(a, b);
+    let (a, b) = (game.spawn(void, Vec2::ZERO), game.spawn(void, Vec2::ZERO));
+    game.drop_on(a, b);
+    game.assert_board(&["Space", "Void", "Void"]);
+}
+
+#[test]
+fn nothing_happens_without_a_recipie() {
+    let mut game = Harness::new("no_recipie", MODS);
+    let a = game.spawn(ItemID::from("Void"), Vec2::ZERO);
+    let b = game.spawn(ItemID::from("Stone"), Vec2::ZERO);
+    game.drop_on(a, b);
+    game.assert_board(&["Stone", "Void"]);
+}
+
+#[test]
+fn trash_takes_many_at_once() {
+    let mut game = Harness::new("trash_many", MODS);
+    let trash = game.spawn(ItemID::from("Trash"), Vec2::ZERO);
+    let void = game.spawn(ItemID::from("Void"), Vec2::new(-100., 0.));
+    let stone = game.spawn(ItemID::from("Stone"), Vec2::new(100., 0.));
+    game.send(ItemEvent::CheckCombine(void, trash));
+    game.send(ItemEvent::CheckCombine(stone, trash));
+    game.step(1);
+    game.assert_board(&["Trash"]);
+    assert_eq!(game.entities(ItemID::from("Trash")), vec![trash]);
+}
+
+#[test]
+fn scripts_hear_combines() {
+    let mut game = Harness::new("script_combine", MODS);
+    game.app.world.resource_mut::<Scripts>()
+        .add("test", r#"fn on_combine(a, b, made, x, y) { if made.contains("Space") { spawn_at("Stone", x, y) } }"#)
+        .unwrap();
+    let void = ItemID::from("Void");
+    let (a, b) = (game.spawn(void, Vec2::ZERO), game.spawn(void, Vec2::ZERO));
+    game.drop_on(a, b);
+    // asked for after the combine, spawned next frame
+    game.step(1);
+    game.assert_board(&["Space", "Stone"]);
+}
+
+#[test]
+fn time_reaches_every_perishable() {
+    let mut game = Harness::new("time_everything", "./assets");
+    // the shipped script with no wait between steps
+    let time = std::fs::read_to_string("./assets/mods/food/time.rhai").unwrap().replace("120.0", "0.0");
+    game.app.world.resource_mut::<Scripts>().add("time", &time).unwrap();
+    let apple = ItemID::from("Apple");
+    for i in 0..40 {
+        game.send(ItemEvent::SpawnAt(apple, Vec3::new((i % 8) as f32 * 120., (i / 8) as f32 * 120., 0.)));
+    }
+    game.step(1);
+    assert_eq!(game.entities(apple).len(), 40);
+    // more than one hook call can ask for, so it takes a few ticks
+    game.step(4);
+    assert!(game.entities(apple).is_empty());
+    assert!(game.board().len() >= 40, "everything advanced was replaced");
+}
+
+#[test]
+fn only_the_player_is_undone() {
+    let mut game = Harness::new("undo_player", MODS);
+    game.app.world.resource_mut::<Scripts>()
+        .add("test", r#"fn on_combine(a, b, made, x, y) { if made.contains("Space") { spawn_at("Stone", x, y) } }"#)
+        .unwrap();
+    let (void, stone) = (ItemID::from("Void"), ItemID::from("Stone"));
+    let (a, b) = (game.spawn(void, Vec2::ZERO), game.spawn(void, Vec2::ZERO));
+    // picking it up is where the action starts
+    game.app.world.resource_mut::<History>().begin();
+    game.drop_on(a, b);
+    game.step(1);
+    game.assert_board(&["Space", "Stone"]);
+    let space = game.entities(ItemID::from("Space"))[0];
+    let step = game.app.world.resource_mut::<History>().undo().unwrap();
+    assert!(step.contains(&Change::Spawned(space, ItemID::from("Space"))));
+    assert!(!step.iter().any(|change| matches!(change, Change::Spawned(_, id) | Change::Found(id) if *id == stone)));
+}
+
+#[test]
+fn board_comes_back_after_a_save() {
+    let mut game = Harness::new("save_load", MODS);
+    let void = game.spawn(ItemID::from("Void"), Vec2::new(-100., 0.));
+    game.spawn(ItemID::from("Stone"), Vec2::new(100., 0.));
+    game.spawn(ItemID::from("Space"), Vec2::new(0., 100.));
+    // the first one spawned is touched last so it is on top
+    game.app.world.get_mut::<DrawOrder>(void).unwrap().0 = 100;
+    game.step(1);
+    let before = game.stack();
+    game.save();
+    let mut loaded = Harness::reopen(&game);
+    loaded.step(2);
+    loaded.assert_board(&["Space", "Stone", "Void"]);
+    assert_eq!(loaded.stack(), before);
+    assert_eq!(before.last().unwrap().0, "Void");
+}
+
+#[test]
+fn locks_and_pins_come_back_after_a_save() {
+    let mut game = Harness::new("lock_pin", MODS);
+    let stone = game.spawn(ItemID::from("Stone"), Vec2::new(100., 0.));
+    game.spawn(ItemID::from("Void"), Vec2::new(-100., 0.));
+    game.app.world.entity_mut(stone).insert(Locked);
+    game.app.world.resource_mut::<Pinned>().0 = vec![ItemID::from("Stone"), ItemID::from("Void")];
+    game.step(1);
+    game.save();
+    let mut loaded = Harness::reopen(&game);
+    loaded.step(2);
+    loaded.assert_board(&["Stone", "Void"]);
+    let stone = loaded.entities(ItemID::from("Stone"))[0];
+    let void = loaded.entities(ItemID::from("Void"))[0];
+    assert!(loaded.app.world.get::<Locked>(stone).is_some());
+    assert!(loaded.app.world.get::<Locked>(void).is_none());
+    assert_eq!(loaded.app.world.resource::<Pinned>().0, vec![ItemID::from("Stone"), ItemID::from("Void")]);
+}
+
+#[test]
+fn newer_save_is_never_written_over() {
+    let newer = "(version: 99, board: [])";
+    let mut game = Harness::with_saves("newer_save", MODS, &[
+        ("save.ron", newer),
+        ("save.old", "(version: 1, board: [(\"Stone\", (100.0, 0.0, 1.0))])"),
+    ]);
+    game.step(2);
+    game.assert_board(&["Stone"]);
+    game.save();
+    game.save();
+    assert_eq!(std::fs::read_to_string(game.file("save.ron.unread")).unwrap(), newer);
+}
+
+#[test]
+fn saved_off_the_board_is_brought_back() {
+    let mut game = Harness::with_saves("off_board", MODS, &[
+        ("save.ron", "(version: 1, board: [(\"Stone\", (100000.0, 0.0, 1.0)), (\"Void\", (100000.0, 0.0, 2.0))])"),
+    ]);
+    game.step(2);
+    game.assert_board(&["Stone", "Void"]);
+    let stone = game.entities(ItemID::from("Stone"))[0];
+    let void = game.entities(ItemID::from("Void"))[0];
+    let stone = game.app.world.get::<Transform>(stone).unwrap().translation;
+    let void = game.app.world.get::<Transform>(void).unwrap().translation;
+    // against the edge it went off, next to each other and still in the order they were saved
+    assert_eq!(stone.truncate(), Vec2::new(3150., 0.));
+    assert!(void.x < 3200. && (void.truncate() - stone.truncate()).abs().max_element() >= 100.);
+    assert!(void.z > stone.z);
+}
+
+#[test]
+fn old_saves_are_migrated_once() {
+    let void = ItemID::from("Void");
+    let made = ron::to_string(&(void, void)).unwrap();
+    let mut game = Harness::with_saves("migrate", MODS, &[
+        ("game.sav", "Void:[-100, 0]\nStone:[100, 0, 2]\n"),
+        ("found.sav", "[\nItem_Space\n]"),
+        ("made.sav", made.as_str()),
+        ("story.sav", "(has_trash: true)"),
+    ]);
+    game.step(2);
+    game.assert_board(&["Stone", "Trash", "Void"]);
+    assert!(game.app.world.resource::<Items>().found().contains(&ItemID::from("Item Space")));
+    assert!(game.app.world.resource::<Recipies>().has_made(void, void));
+    assert!(game.has_file("save.ron"));
+    assert!(!game.has_file("game.sav") && !game.has_file("made.sav"));
+    // the second time round it all comes from the one document
+    let mut loaded = Harness::reopen(&game);
+    loaded.step(2);
+    loaded.assert_board(&["Stone", "Trash", "Void"]);
+    assert!(loaded.app.world.resource::<Recipies>().has_made(void, void));
+}